@@ -0,0 +1,125 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Errors: bad input, simulations that can't go on, and reports that can't
+//   be written. They make their way up to main, which exits with their code
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    // a flag (or scenario variable) that isn't what it should be
+    InvalidValue {
+        name: String,
+        value: String,
+        expected: String,
+    },
+    // options that don't go together, or values out of bounds
+    Config(String),
+    // a scenario or schedule file that can't be used
+    File {
+        file_name: String,
+        reason: String,
+    },
+    // a state the simulation can't go on from
+    Simulation(String),
+    // a report that can't be written
+    Export {
+        file_name: String,
+        source: io::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn invalid_value(name: &str, value: &str, expected: &str) -> Error {
+        Error::InvalidValue {
+            name: name.to_string(),
+            value: value.to_string(),
+            expected: expected.to_string(),
+        }
+    }
+
+    pub fn config(message: &str) -> Error {
+        Error::Config(message.to_string())
+    }
+
+    pub fn file<T: fmt::Display>(file_name: &str, reason: T) -> Error {
+        Error::File {
+            file_name: file_name.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn export(file_name: &str, source: io::Error) -> Error {
+        Error::Export {
+            file_name: file_name.to_string(),
+            source,
+        }
+    }
+
+    // 2 for bad input (as with bad flags), 3 for simulations and 4 for reports
+    pub fn get_exit_code(&self) -> i32 {
+        match self {
+            Error::InvalidValue { .. } | Error::Config(_) | Error::File { .. } => 2,
+            Error::Simulation(_) => 3,
+            Error::Export { .. } => 4,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidValue {
+                name,
+                value,
+                expected,
+            } => write!(f, "invalid {} '{}': expected {}", name, value, expected),
+            Error::Config(message) => write!(f, "{}", message),
+            Error::File { file_name, reason } => write!(f, "{}: {}", file_name, reason),
+            Error::Simulation(message) => write!(f, "simulation failed: {}", message),
+            Error::Export { file_name, source } => {
+                write!(f, "couldn't write to {}: {}", file_name, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Export { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            "invalid epochs 'ten': expected a positive integer",
+            Error::invalid_value("epochs", "ten", "a positive integer").to_string()
+        );
+        assert_eq!(
+            "couldn't write to out.csv: denied",
+            Error::export("out.csv", io::Error::other("denied")).to_string()
+        );
+    }
+
+    #[test]
+    fn get_exit_code() {
+        assert_eq!(2, Error::config("no tiers").get_exit_code());
+        assert_eq!(
+            3,
+            Error::Simulation("no validators".to_string()).get_exit_code()
+        );
+    }
+}
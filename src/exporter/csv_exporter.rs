@@ -0,0 +1,125 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// A header row with the field names, then a row per item
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use super::Exporter;
+use serde::Serialize;
+use std::io::{self, Write};
+
+pub struct CsvExporter;
+
+impl Default for CsvExporter {
+    fn default() -> CsvExporter {
+        CsvExporter::new()
+    }
+}
+
+impl CsvExporter {
+    pub fn new() -> CsvExporter {
+        CsvExporter
+    }
+
+    // quoting and escaping is left to the csv writer. it writes to a
+    //   buffer first, as it would otherwise flush the writer on every row
+    fn to_csv<T: Serialize>(row: &T, has_header: bool) -> io::Result<Vec<u8>> {
+        let mut csv_writer = csv::WriterBuilder::new()
+            .has_headers(has_header)
+            .from_writer(vec![]);
+
+        csv_writer.serialize(row)?;
+        csv_writer.into_inner().map_err(|why| why.into_error())
+    }
+}
+
+// the header is written even without any row, out of the field names
+//   of a default row, so an empty report still says what it would hold
+impl<T: Serialize + Default> Exporter<T> for CsvExporter {
+    fn begin(&mut self, writer: &mut dyn Write) -> io::Result<()> {
+        let contents = CsvExporter::to_csv(&T::default(), true)?;
+
+        // the header line, without the default row
+        let header_length = contents
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .map_or(contents.len(), |position| position + 1);
+        writer.write_all(&contents[..header_length])
+    }
+
+    fn export_row(&mut self, row: &T, writer: &mut dyn Write) -> io::Result<()> {
+        writer.write_all(&CsvExporter::to_csv(row, false)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MonthlyReportRow;
+
+    fn monthly_report_row(initial_staked_balance: u64, month_number: u32) -> MonthlyReportRow {
+        MonthlyReportRow {
+            simulation_id: 0,
+            run: 0,
+            parameters: String::new(),
+            initial_staked_balance,
+            month_number,
+            network_percentage_rewards: 1.5,
+            network_percentage_penalties: 0.5,
+            network_percentage_net_rewards: 1.0,
+        }
+    }
+
+    #[test]
+    fn export() {
+        let rows = vec![
+            monthly_report_row(500_000, 1),
+            monthly_report_row(500_000, 2),
+            monthly_report_row(1_000_000, 1),
+        ];
+        let mut buffer = vec![];
+
+        CsvExporter::new().export(&rows, &mut buffer).unwrap();
+        let contents = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+
+        assert_eq!(
+            "simulation_id,run,parameters,initial_staked_balance,month_number,network_percentage_rewards,network_percentage_penalties,network_percentage_net_rewards",
+            lines[0]
+        );
+        assert_eq!("0,0,,500000,2,1.5,0.5,1.0", lines[2]);
+        assert_eq!(4, lines.len());
+    }
+
+    #[derive(Default, Serialize)]
+    struct Labelled {
+        label: String,
+        value: u64,
+    }
+
+    #[test]
+    fn export_quotes_and_escapes() {
+        let rows = vec![Labelled {
+            label: "stake, \"high\"".to_string(),
+            value: 1,
+        }];
+        let mut buffer = vec![];
+
+        CsvExporter::new().export(&rows, &mut buffer).unwrap();
+
+        assert_eq!(
+            "label,value\n\"stake, \"\"high\"\"\",1\n",
+            String::from_utf8(buffer).unwrap()
+        );
+    }
+
+    #[test]
+    fn export_nothing() {
+        let rows: Vec<Labelled> = vec![];
+        let mut buffer = vec![];
+
+        CsvExporter::new().export(&rows, &mut buffer).unwrap();
+
+        assert_eq!("label,value\n", String::from_utf8(buffer).unwrap());
+    }
+}
@@ -1,58 +1,150 @@
 use super::config::*;
-use std::error::Error;
+use super::{get_exporter, Exporter};
+use crate::error::{Error, Result};
+use serde::Serialize;
 use std::fs::File;
-use std::io::prelude::*;
-use std::path::Path;
-use crate::types::MonthlyReportRow;
+use std::io::{BufWriter, Write};
+use std::time::{Duration, Instant};
 
-pub struct FileExporter { // TODO To make it generic
-    rows: Vec<MonthlyReportRow>
+// how long rows may stay in the buffer, rather than on disk, during a simulation
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+// writes the rows (of any kind) of every simulation to a file as they come,
+//   so partial results are already on disk if a long run gets interrupted.
+//   rows are buffered, but flushed every second and after each simulation
+pub struct FileExporter<T: Serialize + Default> {
+    file_name: String,
+    writer: BufWriter<File>,
+    exporter: Box<dyn Exporter<T>>,
+    last_flush: Instant,
 }
 
-impl FileExporter {
-    pub fn new() -> FileExporter {
-        let rows: Vec<MonthlyReportRow> = vec![];
+impl<T: Serialize + Default> FileExporter<T> {
+    pub fn new(config: &Config) -> Result<FileExporter<T>> {
+        FileExporter::create(&config.output_file_name, &config.output_format)
+    }
+
+    // the statistics of the runs, next to the report
+    pub fn summary(config: &Config) -> Result<FileExporter<T>> {
+        FileExporter::create(&config.get_summary_file_name(), &config.output_format)
+    }
 
-        FileExporter { rows: rows }
+    // the traces of the tracked validators, next to the report
+    pub fn trace(config: &Config) -> Result<FileExporter<T>> {
+        FileExporter::create(&config.get_trace_file_name(), &config.output_format)
     }
 
-    pub fn add_items(&mut self, rows: Vec<MonthlyReportRow>) {
-        self.rows.extend_from_slice(&rows);
+    // the breakdown of the tiers, next to the report
+    pub fn tiers(config: &Config) -> Result<FileExporter<T>> {
+        FileExporter::create(&config.get_tier_file_name(), &config.output_format)
     }
 
-    pub fn export_to_file(&self, config: &Config) {
-        if config.output_format == "json" {       
-            // TODO Move to a method i.e export to json file
-            let file_name = format!("{}", config.output_file_name); // TODO if name contains json, don't add the ext, otherwise add it
-            let path = Path::new(&file_name);
-            let display = path.display();
+    fn create(file_name: &str, output_format: &OutputFormat) -> Result<FileExporter<T>> {
+        let file = File::create(file_name).map_err(|why| Error::export(file_name, why))?;
+
+        let mut file_exporter = FileExporter {
+            file_name: file_name.to_string(),
+            writer: BufWriter::new(file),
+            exporter: get_exporter(output_format),
+            last_flush: Instant::now(),
+        };
 
-            let mut file = match File::create(&path) {
-                Err(why) => panic!("couldn't create {}: {}", display, why.description()),
-                Ok(file) => file,
-            };
+        file_exporter
+            .exporter
+            .begin(&mut file_exporter.writer)
+            .map_err(|why| Error::export(file_name, why))?;
 
-            let json_data = serde_json::to_string(&self.rows)
-                .expect("Couldn't convert to JSON");
+        Ok(file_exporter)
+    }
 
-            match file.write_all(json_data.as_bytes()) {
-                Err(why) => panic!("couldn't write to {}: {}", display, why.description()),
-                Ok(_) => println!("Successfully wrote to {}", display),
-            }
+    pub fn add_item(&mut self, row: &T) -> Result<()> {
+        self.exporter
+            .export_row(row, &mut self.writer)
+            .map_err(|why| Error::export(&self.file_name, why))?;
 
-        } else if config.output_format == "csv" {
-            panic!("Not implemented method");
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush()?;
         }
+
+        Ok(())
+    }
+
+    // the rows so far go to disk, e.g. once a simulation is over
+    pub fn flush(&mut self) -> Result<()> {
+        self.last_flush = Instant::now();
+
+        self.writer
+            .flush()
+            .map_err(|why| Error::export(&self.file_name, why))
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.exporter
+            .end(&mut self.writer)
+            .and_then(|_| self.writer.flush())
+            .map_err(|why| Error::export(&self.file_name, why))?;
+        println!("Successfully wrote to {}", self.file_name);
+
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::EpochReportRow;
+    use std::fs;
+
+    #[test]
+    fn rows_are_on_disk_once_flushed() {
+        let path = std::env::temp_dir().join("simulation_rows_are_on_disk_once_flushed.csv");
+        let file_name = path.to_str().unwrap();
+        let mut file_exporter = FileExporter::create(file_name, &OutputFormat::Csv).unwrap();
+
+        file_exporter
+            .add_item(&EpochReportRow {
+                simulation_id: 3,
+                epoch_id: 7,
+                ..EpochReportRow::new()
+            })
+            .unwrap();
+        // at the end of a simulation, before the report is finished
+        file_exporter.flush().unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert!(lines[0].starts_with("simulation_id,run,parameters,epoch_id,"));
+        assert!(lines[1].starts_with("3,0,,7,"));
+        assert_eq!(2, lines.len());
+
+        file_exporter.add_item(&EpochReportRow::new()).unwrap();
+        file_exporter.finish().unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(3, contents.lines().count());
+    }
 
     #[test]
-    fn get_base_reward() {
+    fn rows_are_flushed_every_second() {
+        let path = std::env::temp_dir().join("simulation_rows_are_flushed_every_second.csv");
+        let file_name = path.to_str().unwrap();
+        let mut file_exporter = FileExporter::create(file_name, &OutputFormat::Csv).unwrap();
 
+        file_exporter.last_flush -= FLUSH_INTERVAL;
+        file_exporter.add_item(&EpochReportRow::new()).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(2, contents.lines().count());
 
+        file_exporter.finish().unwrap();
+        fs::remove_file(&path).unwrap();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn create_in_missing_directory() {
+        let path = std::env::temp_dir().join("simulation_missing_directory/report.csv");
+
+        match FileExporter::<EpochReportRow>::create(path.to_str().unwrap(), &OutputFormat::Csv) {
+            Err(Error::Export { file_name, .. }) => assert!(file_name.ends_with("report.csv")),
+            _ => panic!("expected an export error"),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// All the rows as a single JSON array
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use super::Exporter;
+use serde::Serialize;
+use std::io::{self, Write};
+
+pub struct JsonExporter {
+    is_first_row: bool,
+}
+
+impl Default for JsonExporter {
+    fn default() -> JsonExporter {
+        JsonExporter::new()
+    }
+}
+
+impl JsonExporter {
+    pub fn new() -> JsonExporter {
+        JsonExporter { is_first_row: true }
+    }
+}
+
+// NOTE: the array is only closed at the end, so an interrupted
+//   stream leaves an invalid JSON file behind (NDJSON doesn't)
+impl<T: Serialize> Exporter<T> for JsonExporter {
+    fn begin(&mut self, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, "[")
+    }
+
+    fn export_row(&mut self, row: &T, writer: &mut dyn Write) -> io::Result<()> {
+        if !self.is_first_row {
+            write!(writer, ",")?;
+        }
+        self.is_first_row = false;
+
+        serde_json::to_writer(&mut *writer, row)?;
+        Ok(())
+    }
+
+    fn end(&mut self, writer: &mut dyn Write) -> io::Result<()> {
+        writeln!(writer, "]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MonthlyReportRow;
+
+    #[test]
+    fn export_nothing() {
+        let rows: Vec<MonthlyReportRow> = vec![];
+        let mut buffer = vec![];
+
+        JsonExporter::new().export(&rows, &mut buffer).unwrap();
+
+        assert_eq!("[]\n", String::from_utf8(buffer).unwrap());
+    }
+
+    #[test]
+    fn export() {
+        let rows = vec![MonthlyReportRow {
+            simulation_id: 0,
+            run: 0,
+            parameters: String::new(),
+            initial_staked_balance: 500_000,
+            month_number: 1,
+            network_percentage_rewards: 1.5,
+            network_percentage_penalties: 0.5,
+            network_percentage_net_rewards: 1.0,
+        }];
+        let mut buffer = vec![];
+
+        JsonExporter::new().export(&rows, &mut buffer).unwrap();
+
+        assert_eq!(
+            "[{\"simulation_id\":0,\"run\":0,\"parameters\":\"\",\"initial_staked_balance\":500000,\"month_number\":1,\"network_percentage_rewards\":1.5,\"network_percentage_penalties\":0.5,\"network_percentage_net_rewards\":1.0}]\n",
+            String::from_utf8(buffer).unwrap()
+        );
+    }
+}
@@ -1,3 +1,50 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Exporters write report rows (of any kind) in a given format
+//
+////////////////////////////////////////////////////////////////////////////////
+
+pub mod csv_exporter;
 pub mod file_exporter;
+pub mod json_exporter;
+pub mod ndjson_exporter;
+
+use super::config;
+use config::OutputFormat;
+use csv_exporter::CsvExporter;
+use json_exporter::JsonExporter;
+use ndjson_exporter::NdjsonExporter;
+use serde::Serialize;
+use std::io::{self, Write};
+
+// rows are written one by one, so they can be streamed as they come
+pub trait Exporter<T: Serialize> {
+    // before the first row
+    fn begin(&mut self, _writer: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn export_row(&mut self, row: &T, writer: &mut dyn Write) -> io::Result<()>;
+
+    // after the last row
+    fn end(&mut self, _writer: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn export(&mut self, rows: &[T], writer: &mut dyn Write) -> io::Result<()> {
+        self.begin(writer)?;
+        for row in rows {
+            self.export_row(row, writer)?;
+        }
+        self.end(writer)
+    }
+}
 
-use super::config;
\ No newline at end of file
+// a new format only needs its own exporter and a branch here
+pub fn get_exporter<T: Serialize + Default>(output_format: &OutputFormat) -> Box<dyn Exporter<T>> {
+    match output_format {
+        OutputFormat::Json => Box::new(JsonExporter::new()),
+        OutputFormat::Csv => Box::new(CsvExporter::new()),
+        OutputFormat::Ndjson => Box::new(NdjsonExporter),
+    }
+}
@@ -0,0 +1,42 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// A JSON object per line (newline delimited JSON)
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use super::Exporter;
+use serde::Serialize;
+use std::io::{self, Write};
+
+pub struct NdjsonExporter;
+
+impl<T: Serialize> Exporter<T> for NdjsonExporter {
+    fn export_row(&mut self, row: &T, writer: &mut dyn Write) -> io::Result<()> {
+        serde_json::to_writer(&mut *writer, row)?;
+        writeln!(writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::EpochReportRow;
+
+    #[test]
+    fn export() {
+        let rows: Vec<EpochReportRow> = (0..3)
+            .map(|epoch_id| EpochReportRow {
+                epoch_id,
+                ..EpochReportRow::new()
+            })
+            .collect();
+        let mut buffer = vec![];
+
+        NdjsonExporter.export(&rows, &mut buffer).unwrap();
+        let contents = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+
+        assert_eq!(3, lines.len());
+        assert!(lines[2].starts_with("{\"simulation_id\":0,\"run\":0,\"parameters\":\"\",\"epoch_id\":2,"));
+    }
+}
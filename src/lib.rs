@@ -0,0 +1,28 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// simple simulator of rewards and penalties for Phase 0, as a library:
+//   build a Config, run the simulation, and get its Output back
+//
+////////////////////////////////////////////////////////////////////////////////
+
+pub mod error;
+pub mod exporter;
+pub mod process_epoch;
+pub mod simulator;
+pub mod types;
+
+use types::*;
+
+pub use error::{Error, Result};
+pub use simulator::{run_simulation, run_simulations, start_simulation};
+pub use types::config::{
+    Config, DepositModel, ExitModel, OutputFormat, RewardModel, SlashingModel, Tier,
+    TrackedValidator,
+};
+pub use types::config_builder::ConfigBuilder;
+pub use types::fork::{Fork, ForkParameters, ForkSchedule};
+pub use types::output::{
+    EpochReportRow, MonthlyReportRow, MonthlyTierReportRow, Output, Report, TierReportRow,
+    ValidatorTraceRow,
+};
+pub use types::state::State;
@@ -6,67 +6,81 @@
 
 use crate::types::*;
 
+#[allow(clippy::too_many_arguments)]
 pub fn get_attestation_deltas(
     validator: &Validator,
     validator_index: &usize,
+    epoch_id: i32,
     base_reward: u64,
     config: &Config,
+    parameters: &ForkParameters,
     total_active_balance: u64,
     total_active_validators: u64,
     matching_balance: u64,
-    proposer_indices: &Vec<usize>,
+    finality_delay: u64,
+    proposer_indices: &[usize],
     deltas: &mut Deltas,
 ) {
-    // load our random component
-    let mut dice = Dice::new();
-
     // eligibility check
-    if !validator.is_active {
+    if !validator.is_eligible(epoch_id) {
         return;
     }
 
+    let is_inactivity_leak = finality_delay > config::MIN_EPOCHS_TO_INACTIVITY_PENALTY;
+
+    // SPEC: inactivity penalty, charged to every eligible validator while
+    //   finality is stalled, with a quadratic leak for those missing the target
+    if is_inactivity_leak {
+        deltas.inactivity_penalty = config::BASE_REWARDS_PER_EPOCH * base_reward;
+
+        if validator.is_slashed || !validator.has_attested {
+            deltas.inactivity_penalty += validator.effective_balance * finality_delay
+                / parameters.inactivity_penalty_quotient;
+        }
+    }
+
     // head and FFG incentives (and penalties)
-    if validator.is_slashed
-        || !dice.throw_dice(config.probability_online)
-        || !dice.throw_dice(config.probability_honest)
-    {
+    if validator.is_slashed || !validator.has_attested {
         deltas.head_ffg_penalty = 3 * base_reward;
     } else {
+        // no head rewards during the leak, just source and target
+        let rewarded_votes = if is_inactivity_leak { 2 } else { 3 };
+
         // HACK: avoid integer overflows by "shaving" both balances
         // NOTE: this issue has been reported as of 2020.02.10
         let mb = matching_balance / 1000;
         let tab = total_active_balance / 1000;
-        deltas.head_ffg_reward = 3 * base_reward * mb / tab;
+        deltas.head_ffg_reward = rewarded_votes * base_reward * mb / tab;
 
         // inclusion rewards - proposer
         let proposer_reward_amount = base_reward / config::PROPOSER_REWARD_QUOTIENT;
         if proposer_indices.contains(validator_index) {
             let number_of_attesters = total_active_validators / 32;
-            let number_of_attestations = (number_of_attesters as f32
-                * config.probability_online
-                * config.probability_honest)
-                .floor() as u64;
+            let number_of_attestations =
+                (number_of_attesters as f32 * config.get_expected_participation()).floor() as u64;
             deltas.proposer_reward = proposer_reward_amount * number_of_attestations;
         }
 
         // inclusion rewards - attester
+        let tier = config.get_tier_of(validator);
         let maximum_attester_reward = base_reward - proposer_reward_amount;
         deltas.attester_reward =
-            (maximum_attester_reward as f32 * config.exp_value_inclusion_prob).floor() as u64;
+            (maximum_attester_reward as f32 * tier.exp_value_inclusion_prob).floor() as u64;
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::fork::PHASE0;
     use integer_sqrt::IntegerSquareRoot;
 
     #[test]
     fn non_eligible_validator() {
-        let config = Config::new();
+        let config = Config::default();
         let mut state = State::new(config);
         let mut deltas = Deltas::new();
-        let mut dice = Dice::new();
+        let mut dice = Dice::new(0);
 
         // our validator was not active last epoch
         state.validators[0].is_active = false;
@@ -74,13 +88,17 @@ mod tests {
         // call get_attestation_deltas on your validators
         get_attestation_deltas(
             &state.validators[0],
-            &(0 as usize),
-            state.validators[0].get_base_reward(state.get_total_active_balance().integer_sqrt()),
+            &0_usize,
+            0,
+            state.validators[0]
+                .get_base_reward(state.get_total_active_balance().integer_sqrt(), &PHASE0),
             &state.config,
+            &PHASE0,
             state.get_total_active_balance(),
             state.get_total_active_validators(),
             state.get_matching_balance(),
-            &dice.pick_epoch_proposers(&state),
+            0,
+            &dice.pick_epoch_proposers(&state, &PHASE0).unwrap(),
             &mut deltas,
         );
 
@@ -92,13 +110,13 @@ mod tests {
 
     #[test]
     fn slashed_validator() {
-        let config = Config::new();
+        let config = Config::default();
         let mut state = State::new(config);
         let mut deltas = Deltas::new();
-        let mut dice = Dice::new();
+        let mut dice = Dice::new(0);
 
-        let base_reward =
-            state.validators[0].get_base_reward(state.get_total_active_balance().integer_sqrt());
+        let base_reward = state.validators[0]
+            .get_base_reward(state.get_total_active_balance().integer_sqrt(), &PHASE0);
 
         // our validator was not active last epoch
         state.validators[0].is_slashed = true;
@@ -106,13 +124,16 @@ mod tests {
         // call get_attestation_deltas on your validators
         get_attestation_deltas(
             &state.validators[0],
-            &(0 as usize),
+            &0_usize,
+            0,
             base_reward,
             &state.config,
+            &PHASE0,
             state.get_total_active_balance(),
             state.get_total_active_validators(),
             state.get_matching_balance(),
-            &dice.pick_epoch_proposers(&state),
+            0,
+            &dice.pick_epoch_proposers(&state, &PHASE0).unwrap(),
             &mut deltas,
         );
 
@@ -124,29 +145,35 @@ mod tests {
 
     #[test]
     fn ffg_rewards_1() {
-        let config = Config::new();
+        // arrange for our validator to be always online and honest
+        let config = Config::builder()
+            .probability_online(1.0)
+            .probability_honest(1.0)
+            .build()
+            .unwrap();
         let mut state = State::new(config);
         let mut deltas = Deltas::new();
-        let mut dice = Dice::new();
+        let mut dice = Dice::new(0);
 
-        let base_reward =
-            state.validators[0].get_base_reward(state.get_total_active_balance().integer_sqrt());
+        let base_reward = state.validators[0]
+            .get_base_reward(state.get_total_active_balance().integer_sqrt(), &PHASE0);
 
         state.validators[0].is_active = true;
         state.validators[0].is_slashed = false;
-        state.config.probability_online = 1.0;
-        state.config.probability_honest = 1.0;
 
         // call get_attestation_deltas on your validators
         get_attestation_deltas(
             &state.validators[0],
-            &(0 as usize),
+            &0_usize,
+            0,
             base_reward,
             &state.config,
+            &PHASE0,
             state.get_total_active_balance(),
             state.get_total_active_validators(),
             state.get_matching_balance(),
-            &dice.pick_epoch_proposers(&state),
+            0,
+            &dice.pick_epoch_proposers(&state, &PHASE0).unwrap(),
             &mut deltas,
         );
 
@@ -156,30 +183,36 @@ mod tests {
 
     #[test]
     fn ffg_rewards_2() {
-        let config = Config::new();
+        // arrange for our validator to be always online and honest
+        let config = Config::builder()
+            .probability_online(1.0)
+            .probability_honest(1.0)
+            .build()
+            .unwrap();
         let mut state = State::new(config);
         let mut deltas = Deltas::new();
-        let mut dice = Dice::new();
+        let mut dice = Dice::new(0);
 
-        let base_reward =
-            state.validators[0].get_base_reward(state.get_total_active_balance().integer_sqrt());
+        let base_reward = state.validators[0]
+            .get_base_reward(state.get_total_active_balance().integer_sqrt(), &PHASE0);
 
         state.validators[0].is_active = true;
         state.validators[0].is_slashed = false;
-        state.config.probability_online = 1.0;
-        state.config.probability_honest = 1.0;
         let matching_balance = 2 * state.get_total_active_balance() / 3;
 
         // call get_attestation_deltas on your validators
         get_attestation_deltas(
             &state.validators[0],
-            &(0 as usize),
+            &0_usize,
+            0,
             base_reward,
             &state.config,
+            &PHASE0,
             state.get_total_active_balance(),
             state.get_total_active_validators(),
             matching_balance,
-            &dice.pick_epoch_proposers(&state),
+            0,
+            &dice.pick_epoch_proposers(&state, &PHASE0).unwrap(),
             &mut deltas,
         );
 
@@ -190,28 +223,33 @@ mod tests {
 
     #[test]
     fn proposer_reward_validator_is_proposer() {
-        let config = Config::new();
-        let mut state = State::new(config);
+        // arrange for our validator to be always online and honest
+        let config = Config::builder()
+            .probability_online(1.0)
+            .probability_honest(1.0)
+            .build()
+            .unwrap();
+        let state = State::new(config);
         let mut deltas = Deltas::new();
-        let mut dice = Dice::new();
-        let mut proposer_indices = dice.pick_epoch_proposers(&state);
+        let mut dice = Dice::new(0);
+        let mut proposer_indices = dice.pick_epoch_proposers(&state, &PHASE0).unwrap();
 
         // modify so as to be one of the proposers
         proposer_indices.sort();
         proposer_indices[0] = 0;
 
-        // arrange for our validator to be always online and honest
-        state.config.probability_online = 1.0;
-        state.config.probability_honest = 1.0;
-
         get_attestation_deltas(
             &state.validators[0],
-            &(0 as usize),
-            state.validators[0].get_base_reward(state.get_total_active_balance().integer_sqrt()),
+            &0_usize,
+            0,
+            state.validators[0]
+                .get_base_reward(state.get_total_active_balance().integer_sqrt(), &PHASE0),
             &state.config,
+            &PHASE0,
             state.get_total_active_balance(),
             state.get_total_active_validators(),
             state.get_matching_balance(),
+            0,
             &proposer_indices,
             &mut deltas,
         );
@@ -221,12 +259,17 @@ mod tests {
 
     #[test]
     fn proposer_reward_validator_is_not_proposer() {
-        let config = Config::new();
-        let mut state = State::new(config);
+        // arrange for our validator to be always online and honest
+        let config = Config::builder()
+            .probability_online(1.0)
+            .probability_honest(1.0)
+            .build()
+            .unwrap();
+        let state = State::new(config);
 
         let mut deltas = Deltas::new();
-        let mut dice = Dice::new();
-        let mut proposer_indices = dice.pick_epoch_proposers(&state);
+        let mut dice = Dice::new(0);
+        let mut proposer_indices = dice.pick_epoch_proposers(&state, &PHASE0).unwrap();
 
         // modify so as NOT to be one of the proposers
         proposer_indices.sort();
@@ -234,18 +277,18 @@ mod tests {
             proposer_indices[0] = 1;
         }
 
-        // arrange for our validator to be always online and honest
-        state.config.probability_online = 1.0;
-        state.config.probability_honest = 1.0;
-
         get_attestation_deltas(
             &state.validators[0],
-            &(0 as usize),
-            state.validators[0].get_base_reward(state.get_total_active_balance().integer_sqrt()),
+            &0_usize,
+            0,
+            state.validators[0]
+                .get_base_reward(state.get_total_active_balance().integer_sqrt(), &PHASE0),
             &state.config,
+            &PHASE0,
             state.get_total_active_balance(),
             state.get_total_active_validators(),
             state.get_matching_balance(),
+            0,
             &proposer_indices,
             &mut deltas,
         );
@@ -255,28 +298,129 @@ mod tests {
 
     #[test]
     fn attester_reward() {
-        let config = Config::new();
+        // arrange for our validator to be always online and honest
+        let config = Config::builder()
+            .probability_online(1.0)
+            .probability_honest(1.0)
+            .probability_inclusion(1.0)
+            .build()
+            .unwrap();
+        let state = State::new(config);
+        let mut deltas = Deltas::new();
+        let mut dice = Dice::new(0);
+
+        get_attestation_deltas(
+            &state.validators[0],
+            &0_usize,
+            0,
+            state.validators[0]
+                .get_base_reward(state.get_total_active_balance().integer_sqrt(), &PHASE0),
+            &state.config,
+            &PHASE0,
+            state.get_total_active_balance(),
+            state.get_total_active_validators(),
+            state.get_matching_balance(),
+            0,
+            &dice.pick_epoch_proposers(&state, &PHASE0).unwrap(),
+            &mut deltas,
+        );
+
+        assert_eq!(20_035, deltas.attester_reward);
+    }
+
+    #[test]
+    fn no_inactivity_penalty_before_leak() {
+        let config = Config::default();
         let mut state = State::new(config);
         let mut deltas = Deltas::new();
-        let mut dice = Dice::new();
+        let mut dice = Dice::new(0);
 
-        // arrange for our validator to be always online and honest
-        state.config.probability_online = 1.0;
-        state.config.probability_honest = 1.0;
-        state.config.exp_value_inclusion_prob = 1.0;
+        let base_reward = state.validators[0]
+            .get_base_reward(state.get_total_active_balance().integer_sqrt(), &PHASE0);
+
+        // our validator was offline last epoch
+        state.validators[0].has_attested = false;
 
         get_attestation_deltas(
             &state.validators[0],
-            &(0 as usize),
-            state.validators[0].get_base_reward(state.get_total_active_balance().integer_sqrt()),
+            &0_usize,
+            0,
+            base_reward,
             &state.config,
+            &PHASE0,
             state.get_total_active_balance(),
             state.get_total_active_validators(),
             state.get_matching_balance(),
-            &dice.pick_epoch_proposers(&state),
+            config::MIN_EPOCHS_TO_INACTIVITY_PENALTY,
+            &dice.pick_epoch_proposers(&state, &PHASE0).unwrap(),
             &mut deltas,
         );
 
-        assert_eq!(20_035, deltas.attester_reward);
+        assert_eq!(3 * base_reward, deltas.head_ffg_penalty);
+        assert_eq!(0, deltas.inactivity_penalty);
+    }
+
+    #[test]
+    fn inactivity_penalty_during_leak() {
+        let config = Config::default();
+        let mut state = State::new(config);
+        let mut deltas = Deltas::new();
+        let mut dice = Dice::new(0);
+
+        let base_reward = state.validators[0]
+            .get_base_reward(state.get_total_active_balance().integer_sqrt(), &PHASE0);
+
+        // our validator was offline last epoch
+        state.validators[0].has_attested = false;
+
+        get_attestation_deltas(
+            &state.validators[0],
+            &0_usize,
+            0,
+            base_reward,
+            &state.config,
+            &PHASE0,
+            state.get_total_active_balance(),
+            state.get_total_active_validators(),
+            state.get_matching_balance(),
+            10,
+            &dice.pick_epoch_proposers(&state, &PHASE0).unwrap(),
+            &mut deltas,
+        );
+
+        // 32 ETH * 10 epochs / 2**25
+        assert_eq!(3 * base_reward, deltas.head_ffg_penalty);
+        assert_eq!(4 * base_reward + 9_536, deltas.inactivity_penalty);
+    }
+
+    #[test]
+    fn no_head_reward_during_leak() {
+        let config = Config::default();
+        let state = State::new(config);
+        let mut deltas = Deltas::new();
+        let mut dice = Dice::new(0);
+
+        let base_reward = state.validators[0]
+            .get_base_reward(state.get_total_active_balance().integer_sqrt(), &PHASE0);
+
+        get_attestation_deltas(
+            &state.validators[0],
+            &0_usize,
+            0,
+            base_reward,
+            &state.config,
+            &PHASE0,
+            state.get_total_active_balance(),
+            state.get_total_active_validators(),
+            state.get_matching_balance(),
+            10,
+            &dice.pick_epoch_proposers(&state, &PHASE0).unwrap(),
+            &mut deltas,
+        );
+
+        // only source and target get rewarded, and the base penalty is
+        //   charged anyway, without the quadratic leak
+        assert_eq!(2 * base_reward, deltas.head_ffg_reward);
+        assert_eq!(4 * base_reward, deltas.inactivity_penalty);
     }
 }
@@ -0,0 +1,179 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Simulates the Altair `process_rewards_and_penalties`: each participation
+//   flag of the attestations is rewarded by its weight
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use crate::types::*;
+
+#[allow(clippy::too_many_arguments)]
+pub fn get_flag_index_deltas(
+    validator: &Validator,
+    validator_index: &usize,
+    epoch_id: i32,
+    base_reward: u64,
+    parameters: &ForkParameters,
+    total_active_balance: u64,
+    participating_balances: &[u64; 3],
+    finality_delay: u64,
+    proposer_indices: &[usize],
+    proposer_reward: u64,
+    deltas: &mut Deltas,
+) {
+    // eligibility check
+    if !validator.is_eligible(epoch_id) {
+        return;
+    }
+
+    let is_inactivity_leak = finality_delay > config::MIN_EPOCHS_TO_INACTIVITY_PENALTY;
+    let active_increments = total_active_balance / config::EFFECTIVE_BALANCE_INCREMENT;
+    let is_participating =
+        |flag_index: usize| !validator.is_slashed && validator.participation_flags[flag_index];
+
+    // timely source, target and head rewards (and penalties, but for head)
+    for (flag_index, weight) in config::PARTICIPATION_FLAG_WEIGHTS.iter().enumerate() {
+        if is_participating(flag_index) {
+            // no rewards at all during the leak
+            if !is_inactivity_leak {
+                let participating_increments =
+                    participating_balances[flag_index] / config::EFFECTIVE_BALANCE_INCREMENT;
+                deltas.head_ffg_reward += base_reward * weight * participating_increments
+                    / (active_increments * config::WEIGHT_DENOMINATOR);
+            }
+        } else if flag_index != config::TIMELY_HEAD_FLAG_INDEX {
+            deltas.head_ffg_penalty += base_reward * weight / config::WEIGHT_DENOMINATOR;
+        }
+    }
+
+    // SPEC: get_inactivity_penalty_deltas(), out of the inactivity score
+    //   of those missing the target
+    if !is_participating(config::TIMELY_TARGET_FLAG_INDEX) {
+        deltas.inactivity_penalty = validator.effective_balance * validator.inactivity_score
+            / (config::INACTIVITY_SCORE_BIAS * parameters.inactivity_penalty_quotient);
+    }
+
+    // inclusion rewards - proposer (online and honest, as in phase 0)
+    if validator.has_attested && proposer_indices.contains(validator_index) {
+        deltas.proposer_reward = proposer_reward;
+    }
+}
+
+// SPEC (altair): process_attestation() proposer reward, out of the flags of
+//   the attestations of the epoch, evenly spread over its proposers
+pub fn get_proposer_reward(
+    base_reward_per_increment: u64,
+    participating_balances: &[u64; 3],
+) -> u64 {
+    let proposer_reward_numerator: u64 = config::PARTICIPATION_FLAG_WEIGHTS
+        .iter()
+        .zip(participating_balances.iter())
+        .map(|(weight, balance)| {
+            base_reward_per_increment * (balance / config::EFFECTIVE_BALANCE_INCREMENT) * weight
+        })
+        .sum();
+    let proposer_reward_denominator = (config::WEIGHT_DENOMINATOR - config::PROPOSER_WEIGHT)
+        * config::WEIGHT_DENOMINATOR
+        / config::PROPOSER_WEIGHT;
+
+    proposer_reward_numerator / proposer_reward_denominator / config::SLOTS_PER_EPOCH
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::fork::ALTAIR;
+    use integer_sqrt::IntegerSquareRoot;
+
+    fn get_deltas(state: &State, validator_index: usize, finality_delay: u64) -> Deltas {
+        let mut deltas = Deltas::new();
+        let total_active_balance = state.get_total_active_balance();
+        let base_reward_per_increment =
+            Validator::get_base_reward_per_increment(total_active_balance.integer_sqrt(), &ALTAIR);
+
+        get_flag_index_deltas(
+            &state.validators[validator_index],
+            &validator_index,
+            0,
+            state.validators[validator_index].get_altair_base_reward(base_reward_per_increment),
+            &ALTAIR,
+            total_active_balance,
+            &state.get_unslashed_participating_balances(),
+            finality_delay,
+            &[0],
+            1_000,
+            &mut deltas,
+        );
+
+        deltas
+    }
+
+    #[test]
+    fn full_participation() {
+        let state = State::new(Config::default());
+        let base_reward = 91_584;
+
+        let deltas = get_deltas(&state, 0, 0);
+
+        // every flag, weighted: (14 + 26 + 14) / 64 of the base reward
+        assert_eq!(base_reward * 54 / 64, deltas.head_ffg_reward);
+        assert_eq!(0, deltas.head_ffg_penalty);
+        assert_eq!(1_000, deltas.proposer_reward);
+        assert_eq!(0, get_deltas(&state, 1, 0).proposer_reward);
+    }
+
+    #[test]
+    fn late_head_is_not_penalized() {
+        let mut state = State::new(Config::default());
+        state.validators[0].participation_flags = [true, true, false];
+
+        let deltas = get_deltas(&state, 0, 0);
+
+        // just source and target
+        assert_eq!(91_584 * 14 / 64 + 91_584 * 26 / 64, deltas.head_ffg_reward);
+        assert_eq!(0, deltas.head_ffg_penalty);
+    }
+
+    #[test]
+    fn missed_attestation() {
+        let mut state = State::new(Config::default());
+        state.validators[0].has_attested = false;
+        state.validators[0].participation_flags = [false; 3];
+
+        let deltas = get_deltas(&state, 0, 0);
+
+        // source and target penalties, none for head
+        assert_eq!(0, deltas.head_ffg_reward);
+        assert_eq!(91_584 * 14 / 64 + 91_584 * 26 / 64, deltas.head_ffg_penalty);
+        assert_eq!(0, deltas.proposer_reward);
+        assert_eq!(0, deltas.inactivity_penalty);
+
+        // and out of its score, 32 ETH * 40 / (4 * 3 * 2**24)
+        state.validators[0].inactivity_score = 40;
+        assert_eq!(6_357, get_deltas(&state, 0, 10).inactivity_penalty);
+    }
+
+    #[test]
+    fn no_rewards_during_leak() {
+        let mut state = State::new(Config::default());
+
+        // a score left from an earlier leak doesn't matter when on target
+        state.validators[0].inactivity_score = 40;
+        let deltas = get_deltas(&state, 0, 10);
+
+        assert_eq!(0, deltas.head_ffg_reward);
+        assert_eq!(0, deltas.inactivity_penalty);
+    }
+
+    #[test]
+    fn get_proposer_reward() {
+        // 15,625 validators of 32 increments, all of them participating
+        let balances = [500_000_000_000_000; 3];
+
+        // base rewards * 54 / 448, spread over 32 slots
+        assert_eq!(
+            2_862 * 500_000 * 54 / 448 / 32,
+            super::get_proposer_reward(2_862, &balances)
+        );
+    }
+}
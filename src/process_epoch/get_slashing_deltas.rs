@@ -0,0 +1,163 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Simulates the balance changes of `slash_validator` and `process_slashings`
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use crate::types::*;
+use std::cmp;
+
+#[allow(clippy::too_many_arguments)]
+pub fn get_slashing_deltas(
+    validator: &Validator,
+    validator_index: &usize,
+    epoch_id: i32,
+    parameters: &ForkParameters,
+    total_active_balance: u64,
+    total_slashings: u64,
+    slashings: &[Slashing],
+    deltas: &mut Deltas,
+) {
+    for slashing in slashings {
+        // initial penalty
+        if slashing.validator_index == *validator_index {
+            deltas.slashing_penalty +=
+                slashing.effective_balance / parameters.min_slashing_penalty_quotient;
+        }
+
+        // whistleblower and proposer rewards
+        if slashing.proposer_index == *validator_index {
+            let whistleblower_reward =
+                slashing.effective_balance / parameters.whistleblower_reward_quotient;
+            let proposer_reward = whistleblower_reward / config::PROPOSER_REWARD_QUOTIENT;
+
+            deltas.slashing_proposer_reward += proposer_reward;
+            deltas.whistleblower_reward += whistleblower_reward - proposer_reward;
+        }
+    }
+
+    // midway penalty, correlated with the total slashed in the last vector epochs
+    if validator.is_slashed
+        && epoch_id + config::EPOCHS_PER_SLASHINGS_VECTOR / 2 == validator.withdrawable_epoch
+    {
+        let increment = config::EFFECTIVE_BALANCE_INCREMENT;
+        let penalty_numerator = validator.effective_balance / increment
+            * cmp::min(
+                total_slashings * parameters.proportional_slashing_multiplier,
+                total_active_balance,
+            );
+
+        deltas.midway_penalty = penalty_numerator / total_active_balance * increment;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::fork::{ALTAIR, ELECTRA, PHASE0};
+
+    #[test]
+    fn slashed_validator_and_proposer() {
+        let state = State::new(Config::default());
+        let slashings = vec![Slashing {
+            validator_index: 0,
+            effective_balance: config::MIN_ACTIVATION_BALANCE,
+            proposer_index: 1,
+        }];
+
+        let mut deltas = Deltas::new();
+        get_slashing_deltas(
+            &state.validators[0],
+            &0_usize,
+            10,
+            &PHASE0,
+            state.get_total_active_balance(),
+            0,
+            &slashings,
+            &mut deltas,
+        );
+        assert_eq!(1_000_000_000, deltas.slashing_penalty);
+        assert_eq!(0, deltas.whistleblower_reward);
+
+        let mut deltas = Deltas::new();
+        get_slashing_deltas(
+            &state.validators[1],
+            &1_usize,
+            10,
+            &PHASE0,
+            state.get_total_active_balance(),
+            0,
+            &slashings,
+            &mut deltas,
+        );
+        assert_eq!(0, deltas.slashing_penalty);
+        assert_eq!(7_812_500, deltas.slashing_proposer_reward);
+        assert_eq!(54_687_500, deltas.whistleblower_reward);
+
+        // electra: 1/4096 of the effective balance, for both
+        let mut deltas = Deltas::new();
+        get_slashing_deltas(
+            &state.validators[1],
+            &1_usize,
+            10,
+            &ELECTRA,
+            state.get_total_active_balance(),
+            0,
+            &slashings,
+            &mut deltas,
+        );
+        assert_eq!(976_562, deltas.slashing_proposer_reward);
+        assert_eq!(6_835_938, deltas.whistleblower_reward);
+    }
+
+    #[test]
+    fn midway_penalty() {
+        let mut state = State::new(Config::default());
+        let total_active_balance = state.get_total_active_balance();
+
+        state.validators[0].is_slashed = true;
+        state.validators[0].withdrawable_epoch = 10 + config::EPOCHS_PER_SLASHINGS_VECTOR;
+
+        // one third of the stake slashed: the whole effective balance is gone
+        let mut deltas = Deltas::new();
+        get_slashing_deltas(
+            &state.validators[0],
+            &0_usize,
+            10 + config::EPOCHS_PER_SLASHINGS_VECTOR / 2,
+            &PHASE0,
+            total_active_balance,
+            total_active_balance / 3,
+            &[],
+            &mut deltas,
+        );
+        assert_eq!(31_000_000_000, deltas.midway_penalty);
+
+        // twice as much of it with altair
+        let mut deltas = Deltas::new();
+        get_slashing_deltas(
+            &state.validators[0],
+            &0_usize,
+            10 + config::EPOCHS_PER_SLASHINGS_VECTOR / 2,
+            &ALTAIR,
+            total_active_balance,
+            total_active_balance / 6,
+            &[],
+            &mut deltas,
+        );
+        assert_eq!(10_000_000_000, deltas.midway_penalty);
+
+        // not at the midway epoch
+        let mut deltas = Deltas::new();
+        get_slashing_deltas(
+            &state.validators[0],
+            &0_usize,
+            11,
+            &PHASE0,
+            total_active_balance,
+            total_active_balance / 3,
+            &[],
+            &mut deltas,
+        );
+        assert_eq!(0, deltas.midway_penalty);
+    }
+}
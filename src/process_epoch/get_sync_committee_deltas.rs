@@ -0,0 +1,101 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Simulates the Altair `process_sync_aggregate` of the 32 blocks of the epoch:
+//   the sync committee gets rewarded (or penalized) every slot
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use crate::types::*;
+
+pub fn get_sync_committee_deltas(
+    validator: &Validator,
+    validator_index: &usize,
+    sync_committee: &[usize],
+    participant_reward: u64,
+    participants: u64,
+    proposer_indices: &[usize],
+    deltas: &mut Deltas,
+) {
+    // a validator can sit in the committee more than once
+    let seats = sync_committee
+        .iter()
+        .filter(|index| *index == validator_index)
+        .count() as u64;
+
+    // an online and honest member signs the head of every slot
+    let sync_committee_delta = seats * config::SLOTS_PER_EPOCH * participant_reward;
+    if validator.has_attested {
+        deltas.sync_committee_reward = sync_committee_delta;
+    } else {
+        deltas.sync_committee_penalty = sync_committee_delta;
+    }
+
+    // the proposer of the slot includes the signatures in its block
+    if validator.has_attested && proposer_indices.contains(validator_index) {
+        let proposer_reward = participant_reward * config::PROPOSER_WEIGHT
+            / (config::WEIGHT_DENOMINATOR - config::PROPOSER_WEIGHT);
+        deltas.proposer_reward += participants * proposer_reward;
+    }
+}
+
+// SPEC (altair): process_sync_aggregate() reward of each member, each slot
+pub fn get_sync_participant_reward(
+    base_reward_per_increment: u64,
+    total_active_balance: u64,
+) -> u64 {
+    let total_active_increments = total_active_balance / config::EFFECTIVE_BALANCE_INCREMENT;
+    let total_base_rewards = base_reward_per_increment * total_active_increments;
+    let max_participant_rewards = total_base_rewards * config::SYNC_REWARD_WEIGHT
+        / config::WEIGHT_DENOMINATOR
+        / config::SLOTS_PER_EPOCH;
+
+    max_participant_rewards / config::SYNC_COMMITTEE_SIZE as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_sync_participant_reward() {
+        // 2,862 Gwei per increment of 500,000 ETH, 2/64 of it over 32 slots
+        assert_eq!(
+            2_862 * 500_000 * 2 / 64 / 32 / 512,
+            super::get_sync_participant_reward(2_862, 500_000_000_000_000)
+        );
+    }
+
+    #[test]
+    fn members_get_rewarded_every_slot() {
+        let mut validators = [Validator::new(32_000_000_000); 3];
+        validators[1].has_attested = false;
+        let sync_committee = [0, 1, 0];
+
+        let get_deltas = |validator_index: usize| {
+            let mut deltas = Deltas::new();
+            get_sync_committee_deltas(
+                &validators[validator_index],
+                &validator_index,
+                &sync_committee,
+                100,
+                2,
+                &[2],
+                &mut deltas,
+            );
+            deltas
+        };
+
+        // twice in the committee
+        assert_eq!(2 * 32 * 100, get_deltas(0).sync_committee_reward);
+        assert_eq!(0, get_deltas(0).proposer_reward);
+
+        // offline
+        assert_eq!(0, get_deltas(1).sync_committee_reward);
+        assert_eq!(32 * 100, get_deltas(1).sync_committee_penalty);
+
+        // not in the committee, but proposing a block with 2 signatures
+        assert_eq!(0, get_deltas(2).sync_committee_reward);
+        assert_eq!(0, get_deltas(2).sync_committee_penalty);
+        assert_eq!(2 * (100 * 8 / 56), get_deltas(2).proposer_reward);
+    }
+}
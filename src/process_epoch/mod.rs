@@ -6,68 +6,238 @@
 
 mod apply_deltas;
 mod get_attestation_deltas;
+mod get_flag_index_deltas;
+mod get_slashing_deltas;
+mod get_sync_committee_deltas;
+mod process_attestations;
+mod process_deposits;
+mod process_inactivity_updates;
+mod process_justification_and_finalization;
+mod process_registry_updates;
+mod process_voluntary_exits;
+mod slash_validators;
 
 use integer_sqrt::IntegerSquareRoot;
 use std::time::Instant;
 
+use crate::error::Result;
 use crate::types::*;
 use apply_deltas::*;
 use get_attestation_deltas::*;
+use get_flag_index_deltas::*;
+use get_slashing_deltas::*;
+use get_sync_committee_deltas::*;
+use process_attestations::*;
+use process_deposits::*;
+use process_inactivity_updates::*;
+use process_justification_and_finalization::*;
+use process_registry_updates::*;
+use process_voluntary_exits::*;
+use slash_validators::*;
 
-pub fn process_epoch(pre_state: State, epoch_id: i32, output: &mut Output) -> State {
+pub fn process_epoch(
+    mut pre_state: State,
+    epoch_id: i32,
+    dice: &mut Dice,
+    output: &mut Output,
+) -> Result<State> {
     // start to record
     let mut epoch_report_row = EpochReportRow::new();
+    epoch_report_row.simulation_id = pre_state.config.simulation_id;
+    epoch_report_row.run = pre_state.config.run;
+    epoch_report_row.parameters = pre_state.config.label.clone();
     epoch_report_row.epoch_id = epoch_id;
+
+    // the rules and parameters of the fork of the epoch
+    let fork = pre_state.config.forks.get_fork(epoch_id);
+    let parameters = pre_state.config.forks.get_parameters(epoch_id);
+    let reward_model = fork.get_reward_model();
+    epoch_report_row.fork = fork;
+
     let epoch_processing_start = Instant::now();
+    let mut tier_report_rows: Vec<TierReportRow> = pre_state
+        .config
+        .tiers
+        .iter()
+        .enumerate()
+        .map(|(index, tier)| TierReportRow::new(epoch_id, &tier.name, index))
+        .collect();
+    let mut trace_rows: Vec<ValidatorTraceRow> = vec![];
 
     let mut post_state_validators = vec![];
 
+    // SPEC: is_active_validator() during the previous epoch
+    let previous_epoch = std::cmp::max(epoch_id - 1, config::GENESIS_EPOCH);
+    for validator in pre_state.validators.iter_mut() {
+        validator.is_active = validator.is_active_validator(previous_epoch);
+    }
+
+    // who got a matching attestation included during the previous epoch
+    process_attestations(
+        &mut pre_state.validators,
+        &pre_state.config,
+        reward_model,
+        dice,
+    );
+
     // pre-compute some values that remain constant throughout the epoch
     let total_active_balance = pre_state.get_total_active_balance();
     let sqrt_total_active_balance = total_active_balance.integer_sqrt();
     let total_active_validators = pre_state.get_total_active_validators();
     let matching_balance = pre_state.get_matching_balance();
+    let base_reward_per_increment =
+        Validator::get_base_reward_per_increment(sqrt_total_active_balance, parameters);
+    let participating_balances = pre_state.get_unslashed_participating_balances();
+
+    // SPEC: process_justification_and_finalization()
+    process_justification_and_finalization(
+        &mut pre_state,
+        epoch_id,
+        total_active_balance,
+        matching_balance,
+    );
+    let finality_delay = pre_state.get_finality_delay(epoch_id);
+
+    // SPEC (altair): process_inactivity_updates()
+    let is_altair = reward_model == RewardModel::Altair;
+    if is_altair {
+        process_inactivity_updates(&mut pre_state.validators, epoch_id, finality_delay);
+    }
 
     // pick the 32 block proposers
-    let mut dice = Dice::new();
-    let proposer_indices = dice.pick_epoch_proposers(&pre_state);
+    let proposer_indices = dice.pick_epoch_proposers(&pre_state, parameters)?;
+    let proposer_reward = get_proposer_reward(base_reward_per_increment, &participating_balances);
 
-    for (validator_index, validator) in pre_state.validators.iter().enumerate() {
-        let base_reward = validator.get_base_reward(sqrt_total_active_balance);
+    // SPEC (altair): a new sync committee every period, and a first one
+    //   at the fork
+    if is_altair
+        && (epoch_id % config::EPOCHS_PER_SYNC_COMMITTEE_PERIOD == 0
+            || pre_state.sync_committee.is_empty())
+    {
+        pre_state.sync_committee = dice.pick_sync_committee(&pre_state, parameters)?;
+    }
+    let sync_participant_reward =
+        get_sync_participant_reward(base_reward_per_increment, total_active_balance);
+    let sync_participants = pre_state
+        .sync_committee
+        .iter()
+        .filter(|index| pre_state.validators[**index].has_attested)
+        .count() as u64;
+
+    // SPEC: process_block.process_operations() deposits
+    let deposits = process_deposits(&mut pre_state, epoch_id, dice);
 
+    // SPEC: process_block.process_operations() slashings
+    let slashings = slash_validators(
+        &mut pre_state,
+        epoch_id,
+        parameters,
+        &proposer_indices,
+        dice,
+    );
+    let total_slashings = pre_state.get_total_slashings();
+
+    // SPEC: process_block.process_operations() voluntary exits
+    let voluntary_exits = process_voluntary_exits(&mut pre_state, epoch_id, parameters, dice);
+
+    for (validator_index, validator) in pre_state.validators.iter().enumerate() {
         // SPEC: process_rewards_and_penalties.get_attestation_deltas()
+        //   (get_flag_index_deltas() since altair)
         let mut deltas = Deltas::new();
-        get_attestation_deltas(
-            &validator,
+        match reward_model {
+            RewardModel::Phase0 => get_attestation_deltas(
+                validator,
+                &validator_index,
+                epoch_id,
+                validator.get_base_reward(sqrt_total_active_balance, parameters),
+                &pre_state.config,
+                parameters,
+                total_active_balance,
+                total_active_validators,
+                matching_balance,
+                finality_delay,
+                &proposer_indices,
+                &mut deltas,
+            ),
+            RewardModel::Altair => get_flag_index_deltas(
+                validator,
+                &validator_index,
+                epoch_id,
+                validator.get_altair_base_reward(base_reward_per_increment),
+                parameters,
+                total_active_balance,
+                &participating_balances,
+                finality_delay,
+                &proposer_indices,
+                proposer_reward,
+                &mut deltas,
+            ),
+        }
+
+        // SPEC (altair): process_sync_aggregate()
+        get_sync_committee_deltas(
+            validator,
             &validator_index,
-            base_reward,
-            &pre_state.config,
-            total_active_balance,
-            total_active_validators,
-            matching_balance,
+            &pre_state.sync_committee,
+            sync_participant_reward,
+            sync_participants,
             &proposer_indices,
             &mut deltas,
         );
 
+        // SPEC: slash_validator() and process_slashings()
+        get_slashing_deltas(
+            validator,
+            &validator_index,
+            epoch_id,
+            parameters,
+            total_active_balance,
+            total_slashings,
+            &slashings,
+            &mut deltas,
+        );
+
         // SPEC: process_rewards_and_penalties second half
-        let mut new_validator = apply_deltas(&validator, &deltas);
+        let mut new_validator = apply_deltas(validator, &deltas);
 
         // SPEC: process_final_updates update balances with hysteriesis
-        new_validator.update_effective_balance();
+        new_validator.update_effective_balance(parameters);
+
+        // we follow the tracked validators on their own
+        if let Some(tracked) = validator.tracked {
+            trace_rows.push(ValidatorTraceRow::new(
+                epoch_id,
+                &pre_state.config.tracked_validators[tracked].tier.name,
+                validator_index,
+                &new_validator,
+                &deltas,
+                proposer_indices.contains(&validator_index),
+            ));
+        }
 
         // your new validator gets added to the post_state
         post_state_validators.push(new_validator);
 
         // and we aggregate the rewards and penalties in our records
         epoch_report_row.aggregate(&deltas);
+        if validator.tracked.is_none() {
+            tier_report_rows[validator.tier].aggregate(&deltas);
+        }
     }
 
     // build the new state and record its new totals
-    let post_state = State {
-        config: pre_state.config,
+    let mut post_state = State {
         validators: post_state_validators,
+        ..pre_state
     };
 
+    // SPEC: process_registry_updates()
+    process_registry_updates(&mut post_state, epoch_id, parameters);
+
+    // SPEC: process_final_updates reset the slashings of the next epoch
+    let next_slashings_index = ((epoch_id + 1) % config::EPOCHS_PER_SLASHINGS_VECTOR) as usize;
+    post_state.slashings[next_slashings_index] = 0;
+
     // record and record
     epoch_report_row.total_staked_balance = post_state.get_total_staked_balance();
     epoch_report_row.total_effective_balance = post_state.get_total_active_balance();
@@ -75,10 +245,38 @@ pub fn process_epoch(pre_state: State, epoch_id: i32, output: &mut Output) -> St
     epoch_report_row.min_balance = post_state.get_min_balance();
     epoch_report_row.total_validators = post_state.validators.len() as u64;
     epoch_report_row.total_active_validators = post_state.get_total_active_validators();
+    epoch_report_row.deposits = deposits;
+    epoch_report_row.total_deposited_balance = post_state.total_deposited;
+    epoch_report_row.activation_queue_length = post_state.get_activation_queue_length(epoch_id);
+    epoch_report_row.voluntary_exits = voluntary_exits;
+    epoch_report_row.exit_queue_length = post_state.get_exit_queue_length(epoch_id);
+    epoch_report_row.total_slashed_validators = post_state.get_total_slashed_validators();
+    epoch_report_row.finalized_epoch = post_state.finalized_epoch;
+    epoch_report_row.finality_delay = finality_delay;
     epoch_report_row.time_elapsed = epoch_processing_start.elapsed().as_micros();
     output.push(epoch_report_row);
 
-    post_state
+    for tier_report_row in tier_report_rows.iter_mut() {
+        tier_report_row.simulation_id = post_state.config.simulation_id;
+        tier_report_row.run = post_state.config.run;
+        tier_report_row.parameters = post_state.config.label.clone();
+        tier_report_row.total_validators =
+            post_state.get_total_validators_in_tier(tier_report_row.tier_index);
+        tier_report_row.total_staked_balance =
+            post_state.get_total_staked_balance_in_tier(tier_report_row.tier_index);
+    }
+    output.push_tier_rows(tier_report_rows);
+
+    // the status after the registry updates
+    for trace_row in trace_rows.iter_mut() {
+        trace_row.simulation_id = post_state.config.simulation_id;
+        trace_row.run = post_state.config.run;
+        trace_row.parameters = post_state.config.label.clone();
+        trace_row.status = post_state.validators[trace_row.validator_index].get_status(epoch_id);
+    }
+    output.push_trace_rows(trace_rows);
+
+    Ok(post_state)
 }
 
 // TODO: Test
@@ -0,0 +1,130 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Simulates the attestations included on chain during the previous epoch
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use crate::types::*;
+
+pub fn process_attestations(
+    validators: &mut [Validator],
+    config: &Config,
+    reward_model: RewardModel,
+    dice: &mut Dice,
+) {
+    for validator in validators.iter_mut() {
+        let tier = config.get_tier_of(validator);
+
+        // a vote matches source, target and head if the validator was
+        //   eligible, online and honest
+        validator.has_attested = validator.is_active
+            && !validator.is_slashed
+            && dice.throw_dice(tier.probability_online)
+            && dice.throw_dice(tier.probability_honest);
+
+        // SPEC (altair): such a vote is timely for source and target, and for
+        //   head only when included in the next slot
+        let is_timely_head = validator.has_attested
+            && (reward_model == RewardModel::Phase0 || dice.throw_dice(tier.probability_inclusion));
+        validator.participation_flags = [
+            validator.has_attested,
+            validator.has_attested,
+            is_timely_head,
+        ];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn online_and_honest_validators_attest() {
+        let mut state = State::new(Config::default());
+        let mut dice = Dice::new(0);
+
+        state.config.tiers[0].probability_online = 1.0;
+        state.config.tiers[0].probability_honest = 1.0;
+        state.validators[0].is_slashed = true;
+        state.validators[1].is_active = false;
+
+        process_attestations(
+            &mut state.validators,
+            &state.config,
+            RewardModel::Phase0,
+            &mut dice,
+        );
+
+        assert!(!state.validators[0].has_attested);
+        assert!(!state.validators[1].has_attested);
+        assert!(state.validators[2..].iter().all(|v| v.has_attested));
+    }
+
+    #[test]
+    fn offline_validators_do_not_attest() {
+        let mut state = State::new(Config::default());
+        let mut dice = Dice::new(0);
+
+        state.config.tiers[0].probability_online = 0.0;
+
+        process_attestations(
+            &mut state.validators,
+            &state.config,
+            RewardModel::Phase0,
+            &mut dice,
+        );
+
+        assert_eq!(0, state.get_matching_balance());
+    }
+
+    #[test]
+    fn late_attestations_miss_the_head() {
+        let config = Config::builder()
+            .probability_online(1.0)
+            .probability_inclusion(0.0)
+            .build()
+            .unwrap();
+        let mut state = State::new(config);
+        let mut dice = Dice::new(0);
+
+        process_attestations(
+            &mut state.validators,
+            &state.config,
+            RewardModel::Altair,
+            &mut dice,
+        );
+
+        let balances = state.get_unslashed_participating_balances();
+        assert_eq!(
+            state.get_matching_balance(),
+            balances[config::TIMELY_TARGET_FLAG_INDEX]
+        );
+        assert_eq!(0, balances[config::TIMELY_HEAD_FLAG_INDEX]);
+    }
+
+    #[test]
+    fn tiers_attest_with_their_own_probabilities() {
+        let config = Config::builder()
+            .tier(Tier::new("online", 0.5, 1.0, 1.0, 1.0))
+            .tier(Tier::new("offline", 0.5, 0.0, 1.0, 1.0))
+            .build()
+            .unwrap();
+        let mut state = State::new(config);
+        let mut dice = Dice::new(0);
+
+        process_attestations(
+            &mut state.validators,
+            &state.config,
+            RewardModel::Phase0,
+            &mut dice,
+        );
+
+        for validator in state.validators.iter() {
+            assert_eq!(validator.tier == 0, validator.has_attested);
+        }
+        assert_eq!(
+            state.get_total_validators_in_tier(0) * config::MIN_ACTIVATION_BALANCE,
+            state.get_matching_balance()
+        );
+    }
+}
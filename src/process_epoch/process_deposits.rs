@@ -0,0 +1,84 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Simulates the deposits included during the epoch
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use crate::types::*;
+
+// returns the number of new validators
+pub fn process_deposits(state: &mut State, epoch_id: i32, dice: &mut Dice) -> u64 {
+    let daily_rate = state.config.deposit_model.get_daily_rate(epoch_id);
+    let epoch_rate = daily_rate * 1_000_000_000.0 / config::EPOCHS_PER_DAY;
+
+    let new_validators = match state.config.deposit_model {
+        DepositModel::None => 0,
+        DepositModel::Poisson(_) => {
+            dice.throw_poisson(epoch_rate / config::MIN_ACTIVATION_BALANCE as f64)
+        }
+        DepositModel::Constant(_) | DepositModel::Schedule(_) => {
+            // fractions of a validator are kept for the next epochs
+            state.pending_deposits += epoch_rate as u64;
+            let new_validators = state.pending_deposits / config::MIN_ACTIVATION_BALANCE;
+            state.pending_deposits %= config::MIN_ACTIVATION_BALANCE;
+
+            new_validators
+        }
+    };
+
+    // SPEC: process_deposit() new validators go through the activation queue
+    for _ in 0..new_validators {
+        let tier = state.config.get_tier_by_position(dice.throw_position());
+
+        state.validators.push(Validator {
+            tier,
+            ..Validator::new(config::MIN_ACTIVATION_BALANCE)
+        });
+    }
+    state.total_deposited += new_validators * config::MIN_ACTIVATION_BALANCE;
+
+    new_validators
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_deposits() {
+        // 32 ETH every 10 epochs
+        let config = Config::builder()
+            .deposit_model(DepositModel::Constant(32.0 * config::EPOCHS_PER_DAY / 10.0))
+            .build()
+            .unwrap();
+        let mut state = State::new(config);
+        let mut dice = Dice::new(0);
+        let total_validators = state.validators.len();
+
+        let new_validators: u64 = (0..100)
+            .map(|epoch_id| process_deposits(&mut state, epoch_id, &mut dice))
+            .sum();
+
+        assert!(new_validators == 9 || new_validators == 10);
+        assert_eq!(
+            total_validators + new_validators as usize,
+            state.validators.len()
+        );
+        assert_eq!(
+            new_validators * config::MIN_ACTIVATION_BALANCE,
+            state.total_deposited
+        );
+        assert_eq!(
+            config::FAR_FUTURE_EPOCH,
+            state.validators.last().unwrap().activation_epoch
+        );
+    }
+
+    #[test]
+    fn no_deposits() {
+        let mut state = State::new(Config::default());
+        let mut dice = Dice::new(0);
+
+        assert_eq!(0, process_deposits(&mut state, 0, &mut dice));
+    }
+}
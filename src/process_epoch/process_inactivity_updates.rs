@@ -0,0 +1,90 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Simulates the Altair `process_inactivity_updates` during the state transition
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use crate::types::*;
+
+pub fn process_inactivity_updates(
+    validators: &mut [Validator],
+    epoch_id: i32,
+    finality_delay: u64,
+) {
+    if epoch_id == config::GENESIS_EPOCH {
+        return;
+    }
+
+    let is_inactivity_leak = finality_delay > config::MIN_EPOCHS_TO_INACTIVITY_PENALTY;
+
+    for validator in validators.iter_mut() {
+        if !validator.is_eligible(epoch_id) {
+            continue;
+        }
+
+        // the score goes up for each target missed, and slowly down otherwise
+        let is_on_target = !validator.is_slashed
+            && validator.participation_flags[config::TIMELY_TARGET_FLAG_INDEX];
+        if is_on_target {
+            validator.inactivity_score -= std::cmp::min(1, validator.inactivity_score);
+        } else {
+            validator.inactivity_score += config::INACTIVITY_SCORE_BIAS;
+        }
+
+        // and quickly down once the chain finalizes again
+        if !is_inactivity_leak {
+            validator.inactivity_score -= std::cmp::min(
+                config::INACTIVITY_SCORE_RECOVERY_RATE,
+                validator.inactivity_score,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_grow_during_leak() {
+        let mut validators = [Validator::new(32_000_000_000); 2];
+        for validator in validators.iter_mut() {
+            validator.is_active = true;
+        }
+        validators[1].participation_flags = [false; 3];
+
+        for epoch_id in 1..=10 {
+            process_inactivity_updates(&mut validators, epoch_id, 10);
+        }
+
+        assert_eq!(0, validators[0].inactivity_score);
+        assert_eq!(
+            10 * config::INACTIVITY_SCORE_BIAS,
+            validators[1].inactivity_score
+        );
+
+        // back online, once the chain finalizes: -1 and -16 each epoch
+        validators[1].participation_flags = [true; 3];
+        process_inactivity_updates(&mut validators, 11, 2);
+        assert_eq!(23, validators[1].inactivity_score);
+        process_inactivity_updates(&mut validators, 12, 2);
+        assert_eq!(6, validators[1].inactivity_score);
+        process_inactivity_updates(&mut validators, 13, 2);
+        assert_eq!(0, validators[1].inactivity_score);
+    }
+
+    #[test]
+    fn scores_recover_without_leak() {
+        let mut validators = [Validator::new(32_000_000_000)];
+        validators[0].is_active = true;
+        validators[0].participation_flags = [false; 3];
+
+        // missing the target out of a leak isn't held against it for long
+        process_inactivity_updates(&mut validators, 1, 2);
+        assert_eq!(0, validators[0].inactivity_score);
+
+        // nor at genesis
+        process_inactivity_updates(&mut validators, 0, 10);
+        assert_eq!(0, validators[0].inactivity_score);
+    }
+}
@@ -0,0 +1,137 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Simulates `process_registry_updates` during the state transition
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use crate::types::*;
+
+pub fn process_registry_updates(state: &mut State, epoch_id: i32, parameters: &ForkParameters) {
+    let exit_churn_limit = state.get_exit_churn_limit(parameters);
+    let activation_churn_limit = state.get_activation_churn_limit(parameters);
+
+    // process activation eligibility and ejections
+    for index in 0..state.validators.len() {
+        let validator = &mut state.validators[index];
+
+        if validator.activation_eligibility_epoch == config::FAR_FUTURE_EPOCH
+            && validator.effective_balance >= config::MIN_ACTIVATION_BALANCE
+        {
+            validator.activation_eligibility_epoch = epoch_id + 1;
+        }
+
+        if validator.is_active_validator(epoch_id)
+            && validator.effective_balance <= config::EJECTION_BALANCE
+        {
+            state.initiate_validator_exit(index, epoch_id, exit_churn_limit, parameters);
+        }
+    }
+
+    // queue validators eligible for activation and not yet dequeued for activation
+    let mut activation_queue: Vec<usize> = (0..state.validators.len())
+        .filter(|index| {
+            let validator = &state.validators[*index];
+
+            validator.activation_eligibility_epoch <= state.finalized_epoch
+                && validator.activation_epoch == config::FAR_FUTURE_EPOCH
+        })
+        .collect();
+    activation_queue.sort_by_key(|index| {
+        (
+            state.validators[*index].activation_eligibility_epoch,
+            *index,
+        )
+    });
+
+    // dequeued validators for activation up to churn limit
+    let mut activation_churn = 0;
+    for index in activation_queue {
+        activation_churn += parameters.get_churn(&state.validators[index]);
+        if activation_churn > activation_churn_limit {
+            break;
+        }
+        state.validators[index].activation_epoch = State::compute_activation_exit_epoch(epoch_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::fork::{DENEB, PHASE0};
+
+    #[test]
+    fn ejection() {
+        let mut state = State::new(Config::default());
+
+        state.validators[0].effective_balance = config::EJECTION_BALANCE;
+        state.validators[1].effective_balance = config::EJECTION_BALANCE + 1_000_000_000;
+
+        process_registry_updates(&mut state, 10, &PHASE0);
+
+        assert_eq!(15, state.validators[0].exit_epoch);
+        assert_eq!(config::FAR_FUTURE_EPOCH, state.validators[1].exit_epoch);
+    }
+
+    #[test]
+    fn activation_queue_churn() {
+        let mut state = State::new(Config::default());
+
+        // six new deposits
+        for _ in 0..6 {
+            state
+                .validators
+                .push(Validator::new(config::MIN_ACTIVATION_BALANCE));
+        }
+        let new_validators = state.validators.len() - 6..state.validators.len();
+
+        // they become eligible for the queue, but can't be activated before finality
+        process_registry_updates(&mut state, 10, &PHASE0);
+        for index in new_validators.clone() {
+            assert_eq!(11, state.validators[index].activation_eligibility_epoch);
+            assert_eq!(
+                config::FAR_FUTURE_EPOCH,
+                state.validators[index].activation_epoch
+            );
+        }
+
+        // finality reached: 4 validators get activated per epoch
+        state.finalized_epoch = 11;
+        process_registry_updates(&mut state, 12, &PHASE0);
+        process_registry_updates(&mut state, 13, &PHASE0);
+
+        let activation_epochs: Vec<i32> = new_validators
+            .map(|index| state.validators[index].activation_epoch)
+            .collect();
+        assert_eq!(vec![17, 17, 17, 17, 18, 18], activation_epochs);
+        assert_eq!(6, state.get_activation_queue_length(16));
+        assert_eq!(0, state.get_activation_queue_length(18));
+    }
+
+    #[test]
+    fn activation_churn_cap() {
+        let mut state = State::new(Config::default());
+        // as with 16 x 65,536 validators, more than deneb lets in
+        let parameters = ForkParameters {
+            min_per_epoch_churn_limit: 16,
+            ..DENEB
+        };
+
+        for _ in 0..10 {
+            state
+                .validators
+                .push(Validator::new(config::MIN_ACTIVATION_BALANCE));
+        }
+        let new_validators = state.validators.len() - 10..state.validators.len();
+
+        process_registry_updates(&mut state, 10, &parameters);
+        state.finalized_epoch = 11;
+        process_registry_updates(&mut state, 12, &parameters);
+
+        // just 8 of them, while exits keep the whole churn
+        let activated = new_validators
+            .filter(|index| state.validators[*index].activation_epoch == 17)
+            .count();
+        assert_eq!(8, activated);
+        assert_eq!(16, state.get_exit_churn_limit(&parameters));
+    }
+}
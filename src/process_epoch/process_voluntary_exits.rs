@@ -0,0 +1,56 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Simulates the voluntary exits included during the epoch
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use crate::types::*;
+
+// returns the number of exits initiated
+pub fn process_voluntary_exits(
+    state: &mut State,
+    epoch_id: i32,
+    parameters: &ForkParameters,
+    dice: &mut Dice,
+) -> u64 {
+    let exiting_validators = dice.pick_exiting_validators(state, epoch_id);
+    if exiting_validators.is_empty() {
+        return 0;
+    }
+
+    // SPEC: process_voluntary_exit() goes through the exit queue
+    let churn_limit = state.get_exit_churn_limit(parameters);
+    for validator_index in exiting_validators.iter() {
+        state.initiate_validator_exit(*validator_index, epoch_id, churn_limit, parameters);
+    }
+
+    exiting_validators.len() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::fork::PHASE0;
+
+    #[test]
+    fn exits_go_through_the_queue() {
+        let epoch_id = config::PERSISTENT_COMMITTEE_PERIOD;
+        let config = Config::builder()
+            .exit_model(ExitModel::Schedule(vec![(epoch_id, 10)]))
+            .build()
+            .unwrap();
+        let mut state = State::new(config);
+        let mut dice = Dice::new(0);
+
+        assert_eq!(
+            10,
+            process_voluntary_exits(&mut state, epoch_id, &PHASE0, &mut dice)
+        );
+        assert_eq!(10, state.get_exit_queue_length(epoch_id));
+
+        // 4 validators exit per epoch
+        assert_eq!(6, state.get_exit_queue_length(epoch_id + 5));
+        assert_eq!(2, state.get_exit_queue_length(epoch_id + 6));
+        assert_eq!(0, state.get_exit_queue_length(epoch_id + 7));
+    }
+}
@@ -0,0 +1,80 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Simulates `slash_validator` for the slashings included during the epoch
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use crate::types::*;
+
+pub fn slash_validators(
+    state: &mut State,
+    epoch_id: i32,
+    parameters: &ForkParameters,
+    proposer_indices: &[usize],
+    dice: &mut Dice,
+) -> Vec<Slashing> {
+    let mut slashings = vec![];
+    let churn_limit = state.get_exit_churn_limit(parameters);
+
+    for validator_index in dice.pick_slashed_validators(state, epoch_id) {
+        // SPEC: slashed validators get force-exited
+        state.initiate_validator_exit(validator_index, epoch_id, churn_limit, parameters);
+
+        let validator = &mut state.validators[validator_index];
+        validator.is_slashed = true;
+        validator.withdrawable_epoch = std::cmp::max(
+            validator.withdrawable_epoch,
+            epoch_id + config::EPOCHS_PER_SLASHINGS_VECTOR,
+        );
+        let effective_balance = validator.effective_balance;
+
+        let slashings_index = (epoch_id % config::EPOCHS_PER_SLASHINGS_VECTOR) as usize;
+        state.slashings[slashings_index] += effective_balance;
+
+        // any of the proposers of the epoch may include the slashing
+        slashings.push(Slashing {
+            validator_index,
+            effective_balance,
+            proposer_index: dice.pick_one(proposer_indices),
+        });
+    }
+
+    slashings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::fork::PHASE0;
+
+    #[test]
+    fn slashed_validators_get_force_exited() {
+        let config = Config::builder()
+            .slashing_model(SlashingModel::Schedule(vec![(10, 3)]))
+            .build()
+            .unwrap();
+        let mut state = State::new(config);
+        let mut dice = Dice::new(0);
+        let proposer_indices = dice.pick_epoch_proposers(&state, &PHASE0).unwrap();
+
+        let slashings = slash_validators(&mut state, 10, &PHASE0, &proposer_indices, &mut dice);
+
+        assert_eq!(3, slashings.len());
+        assert_eq!(3, state.get_total_slashed_validators());
+        assert_eq!(
+            3 * config::MIN_ACTIVATION_BALANCE,
+            state.get_total_slashings()
+        );
+
+        for slashing in slashings {
+            let validator = &state.validators[slashing.validator_index];
+
+            assert_eq!(15, validator.exit_epoch);
+            assert_eq!(
+                10 + config::EPOCHS_PER_SLASHINGS_VECTOR,
+                validator.withdrawable_epoch
+            );
+            assert!(proposer_indices.contains(&slashing.proposer_index));
+        }
+    }
+}
@@ -1,15 +1,157 @@
+use super::error::{Error, Result};
 use super::process_epoch::process_epoch;
 use super::types::*;
-// use config::Config;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
 
-pub fn start_simulation(config: Config) -> Output {
+mod report;
+pub use report::report_simulations;
+
+// rows of a simulation waiting to be handled, before it has to wait
+const REPORT_QUEUE_CAPACITY: usize = 1024;
+
+pub fn start_simulation(config: Config) -> Result<Output> {
+    run_simulation(config, Output::new())
+}
+
+// the output may keep the rows in memory, or stream them away
+pub fn run_simulation(config: Config, mut output: Output) -> Result<Output> {
     // println!("start_simulation stake value {}", config.total_at_stake_initial);
     let mut state = State::new(config);
-    let mut output = Output::new();
+    let mut dice = Dice::new(state.config.seed);
 
     for i in 0..state.config.epochs {
-        state = process_epoch(state, i, &mut output);
+        state = process_epoch(state, i, &mut dice, &mut output)?;
+    }
+    output.close_stream();
+
+    Ok(output)
+}
+
+// runs the simulations on a pool of `jobs` threads, and hands over their
+//   reports in the order of the simulations, whatever order they finish in.
+//   streamed rows of a simulation wait (bounded) until the previous ones are done.
+//   the first error, of a simulation or of handling its reports, stops it all
+pub fn run_simulations<F: FnMut(Report) -> Result<()>>(
+    configurations: Vec<Config>,
+    jobs: usize,
+    is_streaming: bool,
+    mut on_report: F,
+) -> Result<()> {
+    let total = configurations.len();
+    let mut receivers: Vec<Receiver<Report>> = vec![];
+    let mut queue: VecDeque<(Config, SyncSender<Report>)> = VecDeque::new();
+
+    for config in configurations {
+        let (tx, rx) = mpsc::sync_channel(REPORT_QUEUE_CAPACITY);
+        receivers.push(rx);
+        queue.push_back((config, tx));
     }
 
-    output
-}
\ No newline at end of file
+    let queue = Arc::new(Mutex::new(queue));
+    let completed = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..std::cmp::min(jobs, total) {
+        let queue = Arc::clone(&queue);
+        let completed = Arc::clone(&completed);
+
+        thread::spawn(move || loop {
+            // simulations are picked in order
+            let job = queue.lock().unwrap().pop_front();
+            let (config, tx) = match job {
+                Some(job) => job,
+                None => break,
+            };
+
+            // rows go away as they come, rather than being kept until the end.
+            //   a panic fails the simulation, rather than leaving it out
+            let config_copy = config.clone();
+            let simulation_id = config.simulation_id;
+            let output = if is_streaming {
+                Output::streaming(&config_copy, tx.clone())
+            } else {
+                Output::new()
+            };
+            let simulation_result =
+                panic::catch_unwind(AssertUnwindSafe(|| run_simulation(config, output)))
+                    .unwrap_or_else(|reason| Err(get_panic_error(simulation_id, reason)));
+
+            let report = match simulation_result {
+                Ok(output) => {
+                    // progress goes to stderr, away from the reports
+                    let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+                    eprintln!("Simulations completed: {}/{}", done, total);
+
+                    Report::Finished(Box::new(config_copy), Box::new(output))
+                }
+                Err(error) => Report::Failed(error),
+            };
+
+            // nobody listens anymore once the run failed
+            if tx.send(report).is_err() {
+                break;
+            }
+        });
+    }
+
+    // a channel closes once its simulation is over
+    for rx in receivers {
+        for report in rx {
+            match report {
+                Report::Failed(error) => return Err(error),
+                report => on_report(report)?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn get_panic_error(simulation_id: u64, reason: Box<dyn Any + Send>) -> Error {
+    let reason = match reason.downcast::<String>() {
+        Ok(reason) => *reason,
+        Err(reason) => reason
+            .downcast_ref::<&str>()
+            .map_or(String::from("unknown reason"), |reason| reason.to_string()),
+    };
+
+    Error::Simulation(format!("simulation {} panicked: {}", simulation_id, reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_simulation_without_argv() {
+        let config = Config::builder().epochs(3).seed(7).build().unwrap();
+
+        let output = start_simulation(config.clone()).unwrap();
+
+        assert_eq!(3, output.rows.len());
+        assert_eq!(2, output.rows[2].epoch_id);
+        // same seed, same simulation
+        let again = start_simulation(config).unwrap();
+        assert_eq!(
+            output.rows[2].total_staked_balance,
+            again.rows[2].total_staked_balance
+        );
+    }
+
+    #[test]
+    fn panic_error() {
+        let reason = panic::catch_unwind(|| panic!("no validator {}", 7)).unwrap_err();
+
+        match get_panic_error(3, reason) {
+            Error::Simulation(message) => {
+                assert_eq!("simulation 3 panicked: no validator 7", message)
+            }
+            _ => panic!("expected a simulation error"),
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use super::run_simulations;
+use crate::error::Result;
+use crate::exporter::file_exporter::FileExporter;
+use crate::types::*;
+
+// runs every simulation of the config (each combination of the sweep, each
+//   run), printing their reports or writing them to the output file
+pub fn report_simulations(config: &Config) -> Result<()> {
+    let is_streaming = !config.output_file_name.is_empty();
+
+    let configurations = config.get_configurations();
+    eprintln!("Number of simulations {}", configurations.len());
+
+    let mut monthly_file_exporter = None;
+    let mut epoch_file_exporter = None;
+    if is_streaming {
+        if config.report_type == "monthly" { // TODO Refactor
+            monthly_file_exporter = Some(FileExporter::new(config)?);
+        } else if config.report_type == "epoch" {
+            epoch_file_exporter = Some(FileExporter::new(config)?);
+        }
+    }
+
+    // statistics of the runs of each simulation, when there are several
+    let has_runs = config.runs > 1;
+    let mut summary = Summary::new(&config.percentiles);
+    let mut summary_file_exporter = None;
+    if is_streaming && has_runs {
+        summary_file_exporter = Some(FileExporter::summary(config)?);
+    }
+
+    // the breakdown of the tiers, and the traces of the tracked
+    //   validators, next to the report too
+    let mut tier_file_exporter = None;
+    let mut monthly_tier_file_exporter = None;
+    if is_streaming && config.tiers.len() > 1 {
+        if config.report_type == "monthly" {
+            monthly_tier_file_exporter = Some(FileExporter::tiers(config)?);
+        } else if config.report_type == "epoch" {
+            tier_file_exporter = Some(FileExporter::tiers(config)?);
+        }
+    }
+    let mut trace_file_exporter = None;
+    if is_streaming && !config.tracked_validators.is_empty() {
+        trace_file_exporter = Some(FileExporter::trace(config)?);
+    }
+
+    // reports are printed (or written) in the order of the simulations
+    run_simulations(configurations, config.jobs, is_streaming, |report| {
+        match report {
+            Report::EpochRow(row) => {
+                if has_runs {
+                    summary.add(&row);
+                }
+                if let Some(file_exporter) = epoch_file_exporter.as_mut() {
+                    file_exporter.add_item(&row)?;
+                }
+            }
+            Report::TierRow(row) => {
+                if let Some(file_exporter) = tier_file_exporter.as_mut() {
+                    file_exporter.add_item(&row)?;
+                }
+            }
+            Report::MonthlyTierRow(row) => {
+                if let Some(file_exporter) = monthly_tier_file_exporter.as_mut() {
+                    file_exporter.add_item(&row)?;
+                }
+            }
+            Report::MonthlyRow(row) => {
+                if has_runs {
+                    summary.add(&row);
+                }
+                if let Some(file_exporter) = monthly_file_exporter.as_mut() {
+                    file_exporter.add_item(&row)?;
+                }
+            }
+            Report::Finished(config_copy, simulation_result) => {
+                // the rows of a whole simulation are on disk
+                if let Some(file_exporter) = epoch_file_exporter.as_mut() {
+                    file_exporter.flush()?;
+                }
+                if let Some(file_exporter) = monthly_file_exporter.as_mut() {
+                    file_exporter.flush()?;
+                }
+                if let Some(file_exporter) = tier_file_exporter.as_mut() {
+                    file_exporter.flush()?;
+                }
+                if let Some(file_exporter) = monthly_tier_file_exporter.as_mut() {
+                    file_exporter.flush()?;
+                }
+
+                if has_runs || !config_copy.label.is_empty() {
+                    print!("Simulation {}", config_copy.simulation_id);
+                    if has_runs {
+                        print!(" run {}", config_copy.run);
+                    }
+                    if !config_copy.label.is_empty() {
+                        print!(" ({})", config_copy.label);
+                    }
+                    println!();
+                }
+
+                if !is_streaming {
+                    if config_copy.report_type == "monthly" { // TODO Refactor      
+                        simulation_result.print_monthly_report(&config_copy)?;
+                        if has_runs {
+                            for row in simulation_result.get_monthly_report(&config_copy).iter() {
+                                summary.add(row);
+                            }
+                        }
+                    } else if config_copy.report_type == "epoch" {
+                        simulation_result.print_epoch_report(&config_copy)?;
+                        if has_runs {
+                            for row in simulation_result.rows.iter() {
+                                summary.add(row);
+                            }
+                        }
+                    }
+                }
+
+                match trace_file_exporter.as_mut() {
+                    Some(file_exporter) => {
+                        for row in simulation_result.trace_rows.iter() {
+                            file_exporter.add_item(row)?;
+                        }
+                    }
+                    None if !config_copy.tracked_validators.is_empty() => {
+                        simulation_result.print_validator_trace(&config_copy)?;
+                    }
+                    None => {}
+                }
+
+                // runs of a simulation come one after the other
+                if has_runs {
+                    summary.end_run();
+
+                    if summary.get_runs() == config_copy.runs {
+                        let summary_rows = summary.take_rows(config_copy.simulation_id, &config_copy.label);
+
+                        match summary_file_exporter.as_mut() {
+                            Some(file_exporter) => {
+                                for row in summary_rows.iter() {
+                                    file_exporter.add_item(row)?;
+                                }
+                            }
+                            None => {
+                                println!("Summary of simulation {} ({} runs)", config_copy.simulation_id, config_copy.runs);
+                                Output::print_summary(&summary_rows, &config_copy)?;
+                            }
+                        }
+                    }
+                }
+            }
+            Report::Failed(error) => return Err(error),
+        }
+
+        Ok(())
+    })?;
+
+    if let Some(file_exporter) = monthly_file_exporter {
+        file_exporter.finish()?;
+    }
+    if let Some(file_exporter) = epoch_file_exporter {
+        file_exporter.finish()?;
+    }
+    if let Some(file_exporter) = summary_file_exporter {
+        file_exporter.finish()?;
+    }
+    if let Some(file_exporter) = tier_file_exporter {
+        file_exporter.finish()?;
+    }
+    if let Some(file_exporter) = monthly_tier_file_exporter {
+        file_exporter.finish()?;
+    }
+    if let Some(file_exporter) = trace_file_exporter {
+        file_exporter.finish()?;
+    }
+
+    Ok(())
+}
@@ -6,13 +6,262 @@
 
 extern crate clap;
 
+use super::config_builder::ConfigBuilder;
+use super::dice::Dice;
+use super::fork::ForkSchedule;
+use crate::error::{Error, Result};
+use super::scenario::{Scenario, ScenarioDeposits};
+use super::sweep::{self, SweepAxis};
+use super::validator::Validator;
 use clap::{App, Arg};
+use std::ffi::OsString;
+use std::fs;
+use std::str::FromStr;
 
-pub const MAX_EFFECTIVE_BALANCE: u64 = 32_000_000_000;
-pub const BASE_REWARD_FACTOR: u64 = 64;
+// the deposit of a new validator (and the most it counts for, without
+//   compounding credentials). the constants that changed with the forks
+//   are in their parameter sets
+pub const MIN_ACTIVATION_BALANCE: u64 = 32_000_000_000;
 pub const BASE_REWARDS_PER_EPOCH: u64 = 4;
 pub const PROPOSER_REWARD_QUOTIENT: u64 = 8;
 pub const EFFECTIVE_BALANCE_INCREMENT: u64 = 1_000_000_000;
+pub const MIN_EPOCHS_TO_INACTIVITY_PENALTY: u64 = 4;
+pub const EJECTION_BALANCE: u64 = 16_000_000_000;
+
+pub const FAR_FUTURE_EPOCH: i32 = i32::MAX;
+pub const GENESIS_EPOCH: i32 = 0;
+pub const MAX_SEED_LOOKAHEAD: i32 = 4;
+pub const MIN_VALIDATOR_WITHDRAWABILITY_DELAY: i32 = 256;
+pub const EPOCHS_PER_SLASHINGS_VECTOR: i32 = 8192;
+pub const PERSISTENT_COMMITTEE_PERIOD: i32 = 2048;
+pub const SLOTS_PER_EPOCH: u64 = 32;
+
+// Altair participation flags, and the weights of the rewards
+pub const TIMELY_SOURCE_FLAG_INDEX: usize = 0;
+pub const TIMELY_TARGET_FLAG_INDEX: usize = 1;
+pub const TIMELY_HEAD_FLAG_INDEX: usize = 2;
+pub const TIMELY_SOURCE_WEIGHT: u64 = 14;
+pub const TIMELY_TARGET_WEIGHT: u64 = 26;
+pub const TIMELY_HEAD_WEIGHT: u64 = 14;
+pub const PROPOSER_WEIGHT: u64 = 8;
+pub const WEIGHT_DENOMINATOR: u64 = 64;
+pub const PARTICIPATION_FLAG_WEIGHTS: [u64; 3] =
+    [TIMELY_SOURCE_WEIGHT, TIMELY_TARGET_WEIGHT, TIMELY_HEAD_WEIGHT];
+
+// Altair sync committees (a period being ~27 hours)
+pub const SYNC_COMMITTEE_SIZE: usize = 512;
+pub const EPOCHS_PER_SYNC_COMMITTEE_PERIOD: i32 = 256;
+pub const SYNC_REWARD_WEIGHT: u64 = 2;
+
+// Altair inactivity scores
+pub const INACTIVITY_SCORE_BIAS: u64 = 4;
+pub const INACTIVITY_SCORE_RECOVERY_RATE: u64 = 16;
+
+// (60 * 60 * 24) / (12 * 32)
+pub const EPOCHS_PER_DAY: f64 = 225.0;
+pub const EPOCHS_PER_YEAR: f64 = EPOCHS_PER_DAY * 365.0;
+// a monthly report splits the epochs of a run in as many months
+pub const MONTHS_PER_YEAR: i32 = 12;
+
+// how reports are written
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Csv,
+    // a JSON object per line
+    Ndjson,
+}
+
+// the rules attestations get rewarded (and penalized) by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RewardModel {
+    // head and FFG votes worth a base reward each, plus inclusion rewards
+    Phase0,
+    // timely source, target and head flags, each with its weight
+    Altair,
+}
+
+impl RewardModel {
+    pub fn parse(reward_model: &str) -> Result<RewardModel> {
+        match reward_model {
+            "phase0" => Ok(RewardModel::Phase0),
+            "altair" => Ok(RewardModel::Altair),
+            _ => Err(Error::invalid_value(
+                "reward model",
+                reward_model,
+                "'phase0' or 'altair'",
+            )),
+        }
+    }
+}
+
+// how validators get slashed during the simulation
+#[derive(Debug, Clone)]
+pub enum SlashingModel {
+    None,
+    // each active validator gets slashed with this probability every epoch
+    Probability(f32),
+    // (epoch, number of validators) to be slashed
+    Schedule(Vec<(i32, u64)>),
+}
+
+// how new validators join the simulation
+#[derive(Debug, Clone)]
+pub enum DepositModel {
+    None,
+    // ETH deposited per day
+    Constant(f64),
+    // (from epoch, ETH deposited per day) piecewise rates
+    Schedule(Vec<(i32, f64)>),
+    // validators arrive as a Poisson process, averaging this ETH per day
+    Poisson(f64),
+}
+
+// how validators voluntarily leave the simulation
+#[derive(Debug, Clone)]
+pub enum ExitModel {
+    None,
+    // each validator requests its exit with this probability every epoch
+    Probability(f32),
+    // validators request their exit once their APR (in %) falls below this
+    AprThreshold(f64),
+    // (epoch, number of validators) requesting their exit
+    Schedule(Vec<(i32, u64)>),
+}
+
+impl DepositModel {
+    // expected ETH deposited per day at a given epoch
+    pub fn get_daily_rate(&self, epoch_id: i32) -> f64 {
+        match self {
+            DepositModel::None => 0.0,
+            DepositModel::Constant(rate) | DepositModel::Poisson(rate) => *rate,
+            DepositModel::Schedule(schedule) => schedule
+                .iter()
+                .rev()
+                .find(|(epoch, _)| *epoch <= epoch_id)
+                .map_or(0.0, |(_, rate)| *rate),
+        }
+    }
+}
+
+// a group of validators sharing the same behaviour
+#[derive(Debug, Clone)]
+pub struct Tier {
+    pub name: String,
+    // share of the validators in this tier
+    pub stake_share: f32,
+
+    // probabilities of any validator of the tier
+    pub probability_online: f32,
+    pub probability_honest: f32,
+    pub probability_inclusion: f32,
+
+    // pre-computation, out of the probability of an attestation being
+    //   included in the next slot
+    pub exp_value_inclusion_prob: f32,
+}
+
+impl Tier {
+    pub fn new(
+        name: &str,
+        stake_share: f32,
+        probability_online: f32,
+        probability_honest: f32,
+        probability_inclusion: f32,
+    ) -> Tier {
+        Tier {
+            name: name.to_string(),
+            stake_share,
+            probability_online,
+            probability_honest,
+            probability_inclusion,
+            exp_value_inclusion_prob: Config::get_exp_value_inclusion_prob(probability_inclusion),
+        }
+    }
+
+    // parses "name:share:online:honest:inclusion"
+    fn parse(tier: &str) -> Result<Tier> {
+        let expected = "name:share:online:honest:inclusion, with values in [0,1]";
+        let parts: Vec<&str> = tier.split(':').map(|part| part.trim()).collect();
+        if parts.len() != 5 {
+            return Err(Error::invalid_value("tier", tier, expected));
+        }
+
+        let values: Vec<f32> = parts[1..]
+            .iter()
+            .map(|value| match value.parse() {
+                Ok(value) if (0.0..=1.0).contains(&value) => Ok(value),
+                _ => Err(Error::invalid_value("tier", tier, expected)),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Tier::new(parts[0], values[0], values[1], values[2], values[3]))
+    }
+}
+
+// a validator we follow through the whole simulation, in a tier of its own
+#[derive(Debug, Clone)]
+pub struct TrackedValidator {
+    // initial balance (in Gwei)
+    pub balance: u64,
+    pub tier: Tier,
+}
+
+impl TrackedValidator {
+    pub fn new(
+        name: &str,
+        balance: u64,
+        probability_online: f32,
+        probability_honest: f32,
+        probability_inclusion: f32,
+    ) -> TrackedValidator {
+        TrackedValidator {
+            balance,
+            tier: Tier::new(
+                name,
+                0.0,
+                probability_online,
+                probability_honest,
+                probability_inclusion,
+            ),
+        }
+    }
+
+    // parses "name:balance:online:honest:inclusion", with the balance in ETH
+    fn parse(tracked_validator: &str) -> Result<TrackedValidator> {
+        let expected =
+            "name:balance:online:honest:inclusion, with a positive balance in ETH and probabilities in [0,1]";
+        let invalid = || Error::invalid_value("tracked validator", tracked_validator, expected);
+        let parts: Vec<&str> = tracked_validator
+            .split(':')
+            .map(|part| part.trim())
+            .collect();
+        if parts.len() != 5 {
+            return Err(invalid());
+        }
+
+        let balance: f64 = match parts[1].parse() {
+            Ok(balance) if balance > 0.0 => balance,
+            _ => return Err(invalid()),
+        };
+
+        let values: Vec<f32> = parts[2..]
+            .iter()
+            .map(|value| match value.parse() {
+                Ok(value) if (0.0..=1.0).contains(&value) => Ok(value),
+                _ => Err(invalid()),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(TrackedValidator::new(
+            parts[0],
+            (balance * 1_000_000_000.0) as u64,
+            values[0],
+            values[1],
+            values[2],
+        ))
+    }
+}
 
 #[derive(Debug,Clone)]
 pub struct Config {
@@ -20,29 +269,82 @@ pub struct Config {
     pub report_type: String,
 
     pub output_file_name: String,
-    // what output format (json) // TODO add csv support
-    pub output_format: String,
+    // what output format (json, csv, ndjson)
+    pub output_format: OutputFormat,
     
     // how many epochs we want to run?
     pub epochs: i32,
 
     // how much ETH we want to start with?
     pub total_at_stake_initial: u64,
-    pub final_stake: u64,
-    pub number_of_simulations: u64,
 
-    // probabilities of any validator
-    pub probability_online: f32,
-    pub probability_honest: f32,
+    // parameters to sweep (a simulation per combination of their values)
+    pub sweep: Vec<SweepAxis>,
+    // which simulation of the sweep this is, and its parameter values
+    pub simulation_id: u64,
+    pub label: String,
+    // how many simulations run at the same time
+    pub jobs: usize,
 
-    // pre-computation
-    pub exp_value_inclusion_prob: f32,
+    // how many times each simulation is repeated (with its own seed),
+    //   which of them this is, and the percentiles of the runs to report
+    pub runs: u64,
+    pub run: u64,
+    pub percentiles: Vec<f64>,
+
+    // seed of the random number generator
+    pub seed: u64,
+
+    // the fork (and so the rules) of each epoch
+    pub forks: ForkSchedule,
+
+    // validators are split in tiers, each one with its own probabilities
+    pub tiers: Vec<Tier>,
+
+    // validators with their own initial balance and probabilities,
+    //   on top of the ones in the tiers
+    pub tracked_validators: Vec<TrackedValidator>,
+
+    pub slashing_model: SlashingModel,
+    pub deposit_model: DepositModel,
+    pub exit_model: ExitModel,
+}
+
+// the defaults of the command line, with a fixed seed, for library users
+impl Default for Config {
+    fn default() -> Config {
+        ConfigBuilder::new()
+            .build()
+            .expect("the defaults should make a valid config")
+    }
 }
 
 impl Config {
-    pub fn new() -> Config {
+    // any scenario, without going through the command line
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+
+    // the command line (and the scenario file it points to)
+    pub fn new() -> Result<Config> {
+        Config::from_args(std::env::args_os())
+    }
+
+    // any command line, the program name first
+    pub fn from_args<I, T>(args: I) -> Result<Config>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
         // parse command line options
         let matches = App::new("Eth2 Reward Simulator")
+            .arg(
+                Arg::with_name("config")
+                    .short("c")
+                    .long("config")
+                    .value_name("file")
+                    .help("Scenario file (TOML, or JSON) with any simulation variable, overridden by the flags"),
+            )
             .arg(
                 Arg::with_name("initial-stake")
                     .short("i")
@@ -55,7 +357,15 @@ impl Config {
                     .short("s")
                     .long("final-stake")
                     .value_name("final_stake")
-                    .help("Your final stake in ETH"),
+                    .help("Your final stake in ETH (sweeps the initial stake up to it)"),
+            )
+            .arg(
+                Arg::with_name("sweep")
+                    .long("sweep")
+                    .value_name("name=start..end:step|name=value,...")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Parameter to sweep (initial-stake, probability-online, probability-honest, epochs), combined with any other"),
             )
             .arg(
                 Arg::with_name("epochs")
@@ -71,6 +381,120 @@ impl Config {
                     .value_name("p")
                     .help("A value in [0,1]"),
             )
+            .arg(
+                Arg::with_name("probability-honest")
+                    .long("probability-honest")
+                    .value_name("p")
+                    .help("Probability of an online validator attesting honestly, a value in [0,1]"),
+            )
+            .arg(
+                Arg::with_name("probability-inclusion")
+                    .long("probability-inclusion")
+                    .value_name("p")
+                    .help("Probability of an attestation being included in the next slot, a value in [0,1]"),
+            )
+            .arg(
+                Arg::with_name("reward-model")
+                    .long("reward-model")
+                    .value_name("model")
+                    .help("Rules attestations get rewarded by (phase0, altair), as that fork from genesis"),
+            )
+            .arg(
+                Arg::with_name("forks")
+                    .long("forks")
+                    .value_name("epoch:fork,...|mainnet")
+                    .help("Forks to go through at given epochs (e.g. 100:altair,200:bellatrix), phase0 until the first one"),
+            )
+            .arg(
+                Arg::with_name("tier")
+                    .short("t")
+                    .long("tier")
+                    .value_name("name:share:online:honest:inclusion")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("A tier of validators, with its share of the stake and probabilities in [0,1]"),
+            )
+            .arg(
+                Arg::with_name("my-validator")
+                    .long("my-validator")
+                    .value_name("name:balance:online:honest:inclusion")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("A validator to trace each epoch, with its balance in ETH and probabilities in [0,1]"),
+            )
+            .arg(
+                Arg::with_name("slashing-probability")
+                    .long("slashing-probability")
+                    .value_name("p")
+                    .help("Probability of any active validator being slashed each epoch"),
+            )
+            .arg(
+                Arg::with_name("slashing-schedule")
+                    .long("slashing-schedule")
+                    .value_name("epoch:validators,...")
+                    .help("Validators to be slashed at given epochs (e.g. 100:10,200:5)"),
+            )
+            .arg(
+                Arg::with_name("deposit-rate")
+                    .long("deposit-rate")
+                    .value_name("ETH")
+                    .help("ETH deposited per day by new validators"),
+            )
+            .arg(
+                Arg::with_name("deposit-schedule")
+                    .long("deposit-schedule")
+                    .value_name("file")
+                    .help("CSV file with the ETH deposited per day from each epoch on (epoch,ETH)"),
+            )
+            .arg(
+                Arg::with_name("deposit-arrivals")
+                    .long("deposit-arrivals")
+                    .value_name("arrivals")
+                    .help("How deposits at --deposit-rate arrive (fixed, poisson)"),
+            )
+            .arg(
+                Arg::with_name("exit-probability")
+                    .long("exit-probability")
+                    .value_name("p")
+                    .help("Probability of any validator requesting its exit each epoch"),
+            )
+            .arg(
+                Arg::with_name("exit-apr-threshold")
+                    .long("exit-apr-threshold")
+                    .value_name("APR")
+                    .help("Validators request their exit when their APR (in %) falls below this"),
+            )
+            .arg(
+                Arg::with_name("exit-schedule")
+                    .long("exit-schedule")
+                    .value_name("file")
+                    .help("CSV file with the validators requesting their exit at each epoch (epoch,validators)"),
+            )
+            .arg(
+                Arg::with_name("jobs")
+                    .short("j")
+                    .long("jobs")
+                    .value_name("jobs")
+                    .help("Simulations to run at the same time (as many as CPUs if omitted)"),
+            )
+            .arg(
+                Arg::with_name("runs")
+                    .long("runs")
+                    .value_name("runs")
+                    .help("Times each simulation is repeated with its own seed, reporting statistics of the runs (1 by default)"),
+            )
+            .arg(
+                Arg::with_name("percentiles")
+                    .long("percentiles")
+                    .value_name("percentiles")
+                    .help("Percentiles of the runs to report, as in 5,50,95 (the default)"),
+            )
+            .arg(
+                Arg::with_name("seed")
+                    .long("seed")
+                    .value_name("seed")
+                    .help("Seed for the random number generator (random if omitted)"),
+            )
             .arg(
                 Arg::with_name("report-type")
                     .short("r")
@@ -90,93 +514,470 @@ impl Config {
                     .short("f")
                     .long("output-format")
                     .value_name("output-format")
-                    .help("Output results format (json, csv)")
+                    .help("Output results format (json, csv, ndjson)")
             )
-            .get_matches();
+            .get_matches_from(args);
+
+        // the scenario file fills in whatever the flags leave out
+        let mut scenario = match matches.value_of("config") {
+            Some(file_name) => Scenario::read(file_name)?,
+            None => Scenario::default(),
+        };
+        let mut builder = Config::builder();
+
+        if let Some(initial_stake) = match matches.value_of("initial-stake") {
+            Some(initial_stake) => Some(Config::parse_value(
+                "initial stake",
+                initial_stake,
+                "an amount of ETH",
+            )?),
+            None => scenario.initial_stake,
+        } {
+            builder = builder.initial_stake(initial_stake);
+        }
+
+        // the final stake sweeps the initial one
+        if let Some(final_stake) = match matches.value_of("final-stake") {
+            Some(final_stake) => Some(Config::parse_value(
+                "final stake",
+                final_stake,
+                "an amount of ETH",
+            )?),
+            None => scenario.final_stake,
+        } {
+            builder = builder.final_stake(final_stake);
+        }
+
+        if let Some(epochs) = match matches.value_of("epochs") {
+            Some(epochs) => Some(Config::parse_value("epochs", epochs, "a positive integer")?),
+            None => scenario.epochs,
+        } {
+            builder = builder.epochs(epochs);
+        }
+
+        // the flags win over the scenario: tiers as flags leave out its
+        //   single-tier probabilities, and probabilities as flags its tiers
+        let has_tier_flags = matches.is_present("tier");
+        let has_probability_flags = [
+            "probability-online",
+            "probability-honest",
+            "probability-inclusion",
+        ]
+        .iter()
+        .any(|name| matches.is_present(name));
+
+        if let Some(probability) = match matches.value_of("probability-online") {
+            Some(probability) => Some(Config::parse_value(
+                "probability online",
+                probability,
+                "a value in [0,1]",
+            )?),
+            None if has_tier_flags => None,
+            None => scenario.probability_online,
+        } {
+            builder = builder.probability_online(probability);
+        }
+
+        if let Some(probability) = match matches.value_of("probability-honest") {
+            Some(probability) => Some(Config::parse_value(
+                "probability honest",
+                probability,
+                "a value in [0,1]",
+            )?),
+            None if has_tier_flags => None,
+            None => scenario.probability_honest,
+        } {
+            builder = builder.probability_honest(probability);
+        }
+
+        // the expected attester reward follows from it
+        if let Some(probability) = match matches.value_of("probability-inclusion") {
+            Some(probability) => Some(Config::parse_value(
+                "probability inclusion",
+                probability,
+                "a value in [0,1]",
+            )?),
+            None if has_tier_flags => None,
+            None => scenario.probability_inclusion,
+        } {
+            builder = builder.probability_inclusion(probability);
+        }
+
+        // the forks, or the single one of a reward model
+        let (reward_model, forks) =
+            if matches.is_present("reward-model") || matches.is_present("forks") {
+                (matches.value_of("reward-model"), matches.value_of("forks"))
+            } else {
+                (scenario.reward_model.as_deref(), scenario.forks.as_deref())
+            };
+        if let Some(reward_model) = reward_model {
+            builder = builder.reward_model(RewardModel::parse(reward_model)?);
+        }
+        if let Some(forks) = forks {
+            builder = builder.forks(ForkSchedule::parse(forks)?);
+        }
+
+        if let Some(report_type) = matches
+            .value_of("report-type")
+            .or(scenario.report_type.as_deref())
+        {
+            builder = builder.report_type(report_type);
+        }
+
+        // tiers (a single one unless told otherwise)
+        let tiers: Vec<Tier> = match matches.values_of("tier") {
+            Some(tiers) => tiers.map(Tier::parse).collect::<Result<_>>()?,
+            None if has_probability_flags => vec![],
+            None => scenario.get_tiers()?,
+        };
+        for tier in tiers {
+            builder = builder.tier(tier);
+        }
 
-        let initial_stake = matches.value_of("initial-stake").unwrap_or("500000"); // TODO MAKE Enum
-        let initial_stake: u64 = match initial_stake.trim().parse() {
-            Ok(num) => num,
-            Err(_) => 500_000,
+        // parameter sweep
+        let sweep: Vec<SweepAxis> = match matches.values_of("sweep") {
+            None => scenario
+                .sweep
+                .iter()
+                .map(|axis| SweepAxis::parse(axis))
+                .collect::<Result<_>>()?,
+            Some(axes) => axes.map(SweepAxis::parse).collect::<Result<_>>()?,
         };
-        
-        if initial_stake < 500_000 { // TODO and multiple of 500,000
-            panic!("initial_stake should be equal or greater than 500000")
+        for axis in sweep {
+            builder = builder.sweep(axis);
         }
 
-        let final_stake = matches.value_of("final-stake").unwrap_or("10000000"); // TODO MAKE Enum
-        let final_stake: u64 = match final_stake.trim().parse() {
-            Ok(num) => num,
-            Err(_) => 10_000_000, // TODO, here should panic
+        // validators to trace
+        let tracked_validators: Vec<TrackedValidator> = match matches.values_of("my-validator") {
+            None => scenario.get_tracked_validators()?,
+            Some(tracked_validators) => tracked_validators
+                .map(TrackedValidator::parse)
+                .collect::<Result<_>>()?,
         };
-        
-        if final_stake > 10_000_000 {
-            panic!("final_stake should be less than 10 000 000")
-        }
-        
-        // ideal default: 81_125 = (60 * 60 * 24 * 365)/(12 * 32)
-        // current default 10
-        let epochs = matches.value_of("epochs").unwrap_or("10");
-        let epochs: i32 = match epochs.trim().parse() {
-            Ok(num) => num,
-            Err(_) => 10,
+        for tracked_validator in tracked_validators {
+            builder = builder.tracked_validator(tracked_validator);
+        }
+
+        // output format
+        let output_format = match matches
+            .value_of("output-format")
+            .or(scenario.output_format.as_deref())
+            .unwrap_or("csv")
+        {
+            "json" => OutputFormat::Json,
+            "csv" => OutputFormat::Csv,
+            "ndjson" => OutputFormat::Ndjson,
+            output_format => {
+                return Err(Error::invalid_value(
+                    "output format",
+                    output_format,
+                    "'json', 'csv' or 'ndjson'",
+                ))
+            }
         };
-        
-        if epochs < 1 {
-            panic!("epoch should be a positive integer")
+        builder = builder.output_format(output_format);
+
+        if let Some(output_file_name) = matches
+            .value_of("output-file-name")
+            .or(scenario.output_file_name.as_deref())
+        {
+            builder = builder.output_file_name(output_file_name);
         }
 
-        let probability = matches.value_of("probability-online").unwrap_or("0.99");   
-        
-        let probability_online = match probability.trim().parse() {
-            Ok(num) => num,
-            Err(_) => 0.99,
+        // slashing model
+        let slashing_model = match (
+            matches.value_of("slashing-probability"),
+            matches.value_of("slashing-schedule"),
+        ) {
+            (Some(_), Some(_)) => {
+                return Err(Error::config(
+                    "choose either a slashing probability or a slashing schedule",
+                ))
+            }
+            (Some(probability), None) => SlashingModel::Probability(Config::parse_value(
+                "slashing probability",
+                probability,
+                "a value in [0,1]",
+            )?),
+            (None, Some(schedule)) => SlashingModel::Schedule(Config::parse_schedule(schedule)?),
+            (None, None) => scenario.get_slashing_model()?,
         };
+        builder = builder.slashing_model(slashing_model);
 
-        if probability_online < 0.0 || probability_online > 1.0 {
-            panic!("probability online should be in the interval [0,1]");
+        // deposit model
+        let deposit_arrivals = matches.value_of("deposit-arrivals").unwrap_or("fixed");
+        if deposit_arrivals != "fixed" && deposit_arrivals != "poisson" {
+            return Err(Error::invalid_value(
+                "deposit arrivals",
+                deposit_arrivals,
+                "'fixed' or 'poisson'",
+            ));
         }
 
-        let number_of_simulations = final_stake / initial_stake;
-        println!("Number of simulations {}", number_of_simulations);
+        let deposit_model = match (
+            matches.value_of("deposit-rate"),
+            matches.value_of("deposit-schedule"),
+        ) {
+            (Some(_), Some(_)) => {
+                return Err(Error::config("choose either a deposit rate or a deposit schedule"))
+            }
+            (Some(rate), None) => {
+                let rate: f64 = Config::parse_value("deposit rate", rate, "an amount of ETH")?;
+
+                if deposit_arrivals == "poisson" {
+                    DepositModel::Poisson(rate)
+                } else {
+                    DepositModel::Constant(rate)
+                }
+            }
+            (None, Some(file_name)) => {
+                if deposit_arrivals == "poisson" {
+                    return Err(Error::config("poisson deposit arrivals need a --deposit-rate"));
+                }
+                DepositModel::Schedule(Config::read_schedule_file(file_name)?)
+            }
+            // the arrivals alone apply to the rate of the scenario
+            (None, None) => {
+                if matches.is_present("deposit-arrivals") {
+                    scenario
+                        .deposits
+                        .get_or_insert_with(ScenarioDeposits::default)
+                        .arrivals = Some(deposit_arrivals.to_string());
+                }
+                scenario.get_deposit_model()?
+            }
+        };
+        builder = builder.deposit_model(deposit_model);
+
+        // exit model
+        let exit_model = match (
+            matches.value_of("exit-probability"),
+            matches.value_of("exit-apr-threshold"),
+            matches.value_of("exit-schedule"),
+        ) {
+            (None, None, None) => scenario.get_exit_model()?,
+            (Some(probability), None, None) => ExitModel::Probability(Config::parse_value(
+                "exit probability",
+                probability,
+                "a value in [0,1]",
+            )?),
+            (None, Some(threshold), None) => ExitModel::AprThreshold(Config::parse_value(
+                "exit APR threshold",
+                threshold,
+                "a number",
+            )?),
+            (None, None, Some(file_name)) => {
+                ExitModel::Schedule(Config::read_schedule_file(file_name)?)
+            }
+            _ => {
+                return Err(Error::config(
+                    "choose either an exit probability, an exit APR threshold or an exit schedule",
+                ))
+            }
+        };
+        builder = builder.exit_model(exit_model);
 
-        let report_type = matches.value_of("report-type").unwrap_or("epoch");
-        if report_type != "epoch" && report_type != "monthly" {
-            panic!("report type only supports 'epoch' or 'monthly'");
+        if let Some(jobs) = match matches.value_of("jobs") {
+            Some(jobs) => Some(Config::parse_value("jobs", jobs, "a positive integer")?),
+            None => scenario.jobs,
+        } {
+            builder = builder.jobs(jobs);
         }
 
-        // stick to 1.0 for now
-        let probability_honest: f32 = 1.0;
+        // monte carlo runs
+        if let Some(runs) = match matches.value_of("runs") {
+            Some(runs) => Some(Config::parse_value("runs", runs, "a positive integer")?),
+            None => scenario.runs,
+        } {
+            builder = builder.runs(runs);
+        }
 
-        // pre-computation
-        let exp_value_inclusion_prob = Config::get_exp_value_inclusion_prob(0.99);
+        let percentiles: Option<Vec<f64>> = match matches.value_of("percentiles") {
+            Some(percentiles) => Some(
+                percentiles
+                    .split(',')
+                    .map(|percentile| {
+                        Config::parse_value("percentile", percentile, "a number in [0,100]")
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            None => scenario.percentiles.clone(),
+        };
+        if let Some(percentiles) = percentiles {
+            builder = builder.percentiles(&percentiles);
+        }
 
-        // output format
-        let output_format = matches.value_of("output-format").unwrap_or("csv");
-        if output_format != "json" && output_format != "csv" {
-            panic!("the only available format is JSON and CSV");
+        // a fixed seed makes the whole run reproducible
+        let seed: u64 = match matches.value_of("seed") {
+            Some(seed) => Config::parse_value("seed", seed, "a positive integer")?,
+            None => scenario.seed.unwrap_or_else(rand::random),
+        };
+        builder.seed(seed).build()
+    }
+
+    // parses a flag value, rejecting anything malformed
+    pub fn parse_value<T: FromStr>(name: &str, value: &str, expected: &str) -> Result<T> {
+        value
+            .trim()
+            .parse()
+            .map_err(|_| Error::invalid_value(name, value, expected))
+    }
+
+    pub fn check_probability(name: &str, probability: f32) -> Result<f32> {
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(Error::Config(format!(
+                "{} should be in the interval [0,1]",
+                name
+            )));
         }
 
-        let output_file_name = matches.value_of("output-file-name").unwrap_or("");
-        
-        Config {
-            output_file_name: output_file_name.to_string(),
-            output_format: output_format.to_string(),
-            report_type: report_type.to_string(),
-            epochs: epochs,
-            total_at_stake_initial: initial_stake * 1_000_000_000,
-            probability_online: probability_online,
-            probability_honest: probability_honest,
-            exp_value_inclusion_prob: exp_value_inclusion_prob,
-            final_stake: final_stake * 1_000_000_000,
-            number_of_simulations: number_of_simulations
+        Ok(probability)
+    }
+
+    // a config for each run of each simulation of the sweep, with its own seed
+    //   and labelled with its parameter values
+    pub fn get_configurations(&self) -> Vec<Config> {
+        let mut configurations = vec![];
+
+        for (index, combination) in sweep::get_combinations(&self.sweep).iter().enumerate() {
+            let mut config = self.clone();
+            config.simulation_id = index as u64;
+
+            for (parameter, value) in combination {
+                parameter.apply(&mut config, *value);
+            }
+            config.label = combination
+                .iter()
+                .map(|(parameter, value)| format!("{}={}", parameter.get_name(), value))
+                .collect::<Vec<String>>()
+                .join(";");
+
+            // with a single run, seeds are the same as without runs
+            for run in 0..self.runs {
+                let mut config = config.clone();
+                config.run = run;
+                config.seed = Dice::derive_seed(self.seed, index as u64 * self.runs + run);
+                configurations.push(config);
+            }
+        }
+
+        configurations
+    }
+
+    // the summary of the runs goes next to the report, as in report.summary.csv
+    pub fn get_summary_file_name(&self) -> String {
+        self.get_file_name_next_to_output("summary")
+    }
+
+    // and so do the traces of the tracked validators, as in report.trace.csv
+    pub fn get_trace_file_name(&self) -> String {
+        self.get_file_name_next_to_output("trace")
+    }
+
+    // and the breakdown of the tiers, as in report.tiers.csv
+    pub fn get_tier_file_name(&self) -> String {
+        self.get_file_name_next_to_output("tiers")
+    }
+
+    fn get_file_name_next_to_output(&self, report: &str) -> String {
+        let file_name_start = self.output_file_name.rfind('/').map_or(0, |slash| slash + 1);
+
+        match self.output_file_name[file_name_start..].rfind('.') {
+            Some(dot) if dot > 0 => {
+                let dot = file_name_start + dot;
+                format!(
+                    "{}.{}{}",
+                    &self.output_file_name[..dot],
+                    report,
+                    &self.output_file_name[dot..]
+                )
+            }
+            _ => format!("{}.{}", self.output_file_name, report),
         }
     }
 
+    // the tier a validator belongs to, out of its position in [0,1) in the registry
+    pub fn get_tier_by_position(&self, position: f32) -> usize {
+        let mut accumulated_share = 0.0;
+
+        for (index, tier) in self.tiers.iter().enumerate() {
+            accumulated_share += tier.stake_share;
+            if position < accumulated_share {
+                return index;
+            }
+        }
+
+        self.tiers.len() - 1
+    }
+
+    // the tier a validator behaves by (tracked validators have their own)
+    pub fn get_tier_of(&self, validator: &Validator) -> &Tier {
+        match validator.tracked {
+            Some(index) => &self.tracked_validators[index].tier,
+            None => &self.tiers[validator.tier],
+        }
+    }
+
+    // expected share of the validators attesting correctly
+    pub fn get_expected_participation(&self) -> f32 {
+        self.tiers
+            .iter()
+            .map(|tier| tier.stake_share * tier.probability_online * tier.probability_honest)
+            .sum()
+    }
+
+    // parses "epoch:amount,epoch:amount,..."
+    pub fn parse_schedule<T: FromStr>(schedule: &str) -> Result<Vec<(i32, T)>> {
+        schedule
+            .split(',')
+            .map(|entry| {
+                let mut parts = entry.trim().split(':');
+                let epoch = parts.next().and_then(|e| e.trim().parse().ok());
+                let amount = parts.next().and_then(|a| a.trim().parse().ok());
+
+                match (epoch, amount, parts.next()) {
+                    (Some(epoch), Some(amount), None) => Ok((epoch, amount)),
+                    _ => Err(Error::invalid_value("schedule entry", entry, "epoch:amount")),
+                }
+            })
+            .collect()
+    }
+
+    // reads a CSV file of "epoch,amount" lines, sorted by epoch.
+    //   a header line is allowed
+    pub fn read_schedule_file<T: FromStr>(file_name: &str) -> Result<Vec<(i32, T)>> {
+        let contents =
+            fs::read_to_string(file_name).map_err(|why| Error::file(file_name, why))?;
+
+        let mut schedule =
+            Config::parse_schedule_csv(&contents).map_err(|why| Error::file(file_name, why))?;
+        schedule.sort_by_key(|(epoch, _)| *epoch);
+
+        Ok(schedule)
+    }
+
+    fn parse_schedule_csv<T: FromStr>(contents: &str) -> Result<Vec<(i32, T)>> {
+        contents
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let mut parts = line.split(',');
+                let epoch = parts.next().and_then(|e| e.trim().parse().ok());
+                let amount = parts.next().and_then(|a| a.trim().parse().ok());
+
+                match (epoch, amount, parts.next()) {
+                    (Some(epoch), Some(amount), None) => Some(Ok((epoch, amount))),
+                    _ if index == 0 => None,
+                    _ => Some(Err(Error::invalid_value("schedule line", line, "epoch,amount"))),
+                }
+            })
+            .collect()
+    }
+
     fn get_exp_value_inclusion_prob(p: f32) -> f32 {
-        if p == 0.0 {
-            p
-        } else if p == 1.0 {
+        if p == 0.0 || p == 1.0 {
             p
         } else {
             p * p.ln() / (p - 1.00)
@@ -212,6 +1013,233 @@ mod tests {
         );
     }
 
+    #[test]
+    fn tier_inclusion_probability() {
+        let tier = Tier::new("all", 1.0, 0.99, 0.9, 0.9);
+
+        // it no longer comes out of 0.99 regardless
+        assert_eq!(0.94, truncate_two(tier.exp_value_inclusion_prob));
+        assert_eq!(0.9, tier.probability_honest);
+    }
+
+    #[test]
+    fn parse_tier() {
+        let tier = Tier::parse("A: 0.25:0.9:1.0:0.95").unwrap();
+
+        assert_eq!("A", tier.name);
+        assert_eq!(0.25, tier.stake_share);
+        assert_eq!(0.9, tier.probability_online);
+        assert_eq!(1.0, tier.probability_honest);
+        assert_eq!(0.97, truncate_two(tier.exp_value_inclusion_prob));
+    }
+
+    #[test]
+    fn parse_invalid_tier() {
+        assert!(Tier::parse("A:0.25:0.9:1.0").is_err());
+        assert!(Tier::parse("A:0.25:1.5:1.0:0.95").is_err());
+        assert!(Tier::parse("A:0.25:x:1.0:0.95").is_err());
+    }
+
+    #[test]
+    fn parse_tracked_validator() {
+        let tracked_validator = TrackedValidator::parse("mine:33.5:0.9:1.0:0.95").unwrap();
+
+        assert_eq!("mine", tracked_validator.tier.name);
+        assert_eq!(33_500_000_000, tracked_validator.balance);
+        assert_eq!(0.9, tracked_validator.tier.probability_online);
+        assert_eq!(1.0, tracked_validator.tier.probability_honest);
+    }
+
+    #[test]
+    fn get_tier_by_position() {
+        let config = Config::builder()
+            .tier(Tier::new("A", 0.25, 1.0, 1.0, 1.0))
+            .tier(Tier::new("B", 0.5, 0.5, 1.0, 1.0))
+            .tier(Tier::new("C", 0.25, 0.0, 1.0, 1.0))
+            .build()
+            .unwrap();
+
+        assert_eq!(0, config.get_tier_by_position(0.0));
+        assert_eq!(1, config.get_tier_by_position(0.25));
+        assert_eq!(1, config.get_tier_by_position(0.7));
+        assert_eq!(2, config.get_tier_by_position(0.75));
+        assert_eq!(2, config.get_tier_by_position(0.99));
+        assert_eq!(0.5, config.get_expected_participation());
+    }
+
+    #[test]
+    fn get_configurations() {
+        let config = Config::builder()
+            .sweep(SweepAxis::parse("initial-stake=500000,1000000").unwrap())
+            .sweep(SweepAxis::parse("probability-online=0.5..1:0.25").unwrap())
+            .build()
+            .unwrap();
+
+        let configurations = config.get_configurations();
+
+        assert_eq!(6, configurations.len());
+        assert_eq!(4, configurations[4].simulation_id);
+        assert_eq!(1_000_000_000_000_000, configurations[4].total_at_stake_initial);
+        assert_eq!(0.75, configurations[4].tiers[0].probability_online);
+        assert_eq!(
+            "initial-stake=1000000;probability-online=0.75",
+            configurations[4].label
+        );
+        assert_ne!(configurations[0].seed, configurations[1].seed);
+    }
+
+    #[test]
+    fn get_configurations_without_sweep() {
+        let config = Config::default();
+
+        let configurations = config.get_configurations();
+
+        assert_eq!(1, configurations.len());
+        assert_eq!(config.total_at_stake_initial, configurations[0].total_at_stake_initial);
+        assert_eq!("", configurations[0].label);
+    }
+
+    #[test]
+    fn get_configurations_with_runs() {
+        let builder = Config::builder().sweep(SweepAxis::parse("epochs=10,20").unwrap());
+        let single_run_seeds: Vec<u64> = builder
+            .clone()
+            .build()
+            .unwrap()
+            .get_configurations()
+            .iter()
+            .map(|configuration| configuration.seed)
+            .collect();
+
+        let configurations = builder.runs(3).build().unwrap().get_configurations();
+
+        assert_eq!(6, configurations.len());
+        assert_eq!(1, configurations[4].simulation_id);
+        assert_eq!(1, configurations[4].run);
+        assert_eq!(20, configurations[4].epochs);
+        assert_ne!(configurations[3].seed, configurations[4].seed);
+        assert_eq!(single_run_seeds[0], configurations[0].seed);
+    }
+
+    #[test]
+    fn get_summary_file_name() {
+        let config = Config::builder().output_file_name("out/report.csv").build().unwrap();
+        assert_eq!("out/report.summary.csv", config.get_summary_file_name());
+
+        let config = Config::builder().output_file_name("./report").build().unwrap();
+        assert_eq!("./report.summary", config.get_summary_file_name());
+        assert_eq!("./report.trace", config.get_trace_file_name());
+        assert_eq!("./report.tiers", config.get_tier_file_name());
+    }
+
+    #[test]
+    fn parse_schedule() {
+        assert_eq!(
+            vec![(100, 10), (200, 5)],
+            Config::parse_schedule("100:10, 200:5").unwrap()
+        );
+        assert!(Config::parse_schedule::<u64>("100:10,200").is_err());
+    }
+
+    #[test]
+    fn parse_value() {
+        assert_eq!(10, Config::parse_value::<i32>("epochs", " 10", "").unwrap());
+
+        // malformed numbers aren't replaced by a default
+        match Config::parse_value::<i32>("epochs", "1O", "a positive integer") {
+            Err(Error::InvalidValue { name, value, .. }) => {
+                assert_eq!("epochs", name);
+                assert_eq!("1O", value);
+            }
+            _ => panic!("expected an invalid value"),
+        }
+        assert!(Config::check_probability("probability online", 1.01).is_err());
+    }
+
+    #[test]
+    fn parse_schedule_csv() {
+        assert_eq!(
+            vec![(0, 1000.0), (100, 2500.5)],
+            Config::parse_schedule_csv("epoch,ETH per day\n0,1000\n\n100, 2500.5\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn deposit_model_daily_rate() {
+        let schedule = DepositModel::Schedule(vec![(10, 100.0), (20, 50.0)]);
+
+        assert_eq!(0.0, schedule.get_daily_rate(5));
+        assert_eq!(100.0, schedule.get_daily_rate(10));
+        assert_eq!(100.0, schedule.get_daily_rate(19));
+        assert_eq!(50.0, schedule.get_daily_rate(1000));
+    }
+
+    // the config out of the flags, along with a scenario file
+    fn from_scenario_and_args(name: &str, scenario: &str, args: &[&str]) -> Result<Config> {
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, scenario).unwrap();
+
+        let config = Config::from_args(
+            ["simulation", "-c", path.to_str().unwrap(), "--seed", "7"]
+                .iter()
+                .chain(args.iter()),
+        );
+        fs::remove_file(&path).unwrap();
+
+        config
+    }
+
+    #[test]
+    fn tier_flags_override_scenario_probabilities() {
+        let config = from_scenario_and_args(
+            "simulation_tier_flags.toml",
+            "probability-online = 0.5\nprobability-honest = 0.5\n",
+            &["-t", "A:0.5:0.9:1:0.99", "-t", "B:0.5:0.8:1:0.99"],
+        )
+        .unwrap();
+
+        assert_eq!(2, config.tiers.len());
+        assert_eq!(0.9, config.tiers[0].probability_online);
+        assert_eq!(1.0, config.tiers[1].probability_honest);
+    }
+
+    #[test]
+    fn probability_flags_override_scenario_tiers() {
+        let config = from_scenario_and_args(
+            "simulation_probability_flags.toml",
+            "[[tiers]]\nname = \"A\"\nshare = 1.0\nonline = 0.5\ninclusion = 0.99\n",
+            &["-p", "0.9"],
+        )
+        .unwrap();
+
+        assert_eq!(1, config.tiers.len());
+        assert_eq!("all", config.tiers[0].name);
+        assert_eq!(0.9, config.tiers[0].probability_online);
+    }
+
+    #[test]
+    fn deposit_arrivals_flag_keeps_scenario_rate() {
+        let config = from_scenario_and_args(
+            "simulation_deposit_arrivals.toml",
+            "[deposits]\nrate = 100000.0\n",
+            &["--deposit-arrivals", "poisson"],
+        )
+        .unwrap();
+
+        match config.deposit_model {
+            DepositModel::Poisson(rate) => assert_eq!(100_000.0, rate),
+            _ => panic!("expected poisson deposits"),
+        }
+
+        // without a rate, in the scenario or as a flag
+        assert!(from_scenario_and_args(
+            "simulation_deposit_arrivals_alone.toml",
+            "",
+            &["--deposit-arrivals", "poisson"],
+        )
+        .is_err());
+    }
+
     fn truncate_two(number: f32) -> f32 {
         (number * 100.0).floor() / 100.0
     }
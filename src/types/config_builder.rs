@@ -0,0 +1,523 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Builds a Config out of any simulation variable, checking them all,
+//   without going through the command line
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use super::config::{
+    Config, DepositModel, ExitModel, OutputFormat, RewardModel, SlashingModel, Tier,
+    TrackedValidator, MONTHS_PER_YEAR,
+};
+use super::fork::{Fork, ForkSchedule};
+use super::sweep::{SweepAxis, SweepParameter};
+use crate::error::{Error, Result};
+
+// starts with the defaults of the command line, with a fixed seed
+#[derive(Debug, Clone)]
+pub struct ConfigBuilder {
+    report_type: String,
+    output_file_name: String,
+    output_format: OutputFormat,
+    epochs: i32,
+
+    // in ETH
+    initial_stake: u64,
+    final_stake: Option<u64>,
+
+    // of the single tier, when no tiers are given
+    probability_online: Option<f32>,
+    probability_honest: Option<f32>,
+    probability_inclusion: Option<f32>,
+
+    // phase0 from genesis when neither is given
+    reward_model: Option<RewardModel>,
+    forks: Option<ForkSchedule>,
+    tiers: Vec<Tier>,
+    tracked_validators: Vec<TrackedValidator>,
+    sweep: Vec<SweepAxis>,
+
+    slashing_model: SlashingModel,
+    deposit_model: DepositModel,
+    exit_model: ExitModel,
+
+    seed: u64,
+    // as many as CPUs when not given
+    jobs: Option<usize>,
+    runs: u64,
+    percentiles: Vec<f64>,
+}
+
+impl Default for ConfigBuilder {
+    fn default() -> ConfigBuilder {
+        ConfigBuilder::new()
+    }
+}
+
+impl ConfigBuilder {
+    pub fn new() -> ConfigBuilder {
+        ConfigBuilder {
+            report_type: String::from("epoch"),
+            output_file_name: String::new(),
+            output_format: OutputFormat::Csv,
+            // ideal: 81_125 = (60 * 60 * 24 * 365)/(12 * 32)
+            epochs: 10,
+            initial_stake: 500_000,
+            final_stake: None,
+            probability_online: None,
+            probability_honest: None,
+            probability_inclusion: None,
+            reward_model: None,
+            forks: None,
+            tiers: vec![],
+            tracked_validators: vec![],
+            sweep: vec![],
+            slashing_model: SlashingModel::None,
+            deposit_model: DepositModel::None,
+            exit_model: ExitModel::None,
+            seed: 0,
+            jobs: None,
+            runs: 1,
+            percentiles: vec![5.0, 50.0, 95.0],
+        }
+    }
+
+    // epoch or monthly
+    pub fn report_type(mut self, report_type: &str) -> ConfigBuilder {
+        self.report_type = report_type.to_string();
+        self
+    }
+
+    pub fn output_file_name(mut self, output_file_name: &str) -> ConfigBuilder {
+        self.output_file_name = output_file_name.to_string();
+        self
+    }
+
+    pub fn output_format(mut self, output_format: OutputFormat) -> ConfigBuilder {
+        self.output_format = output_format;
+        self
+    }
+
+    pub fn epochs(mut self, epochs: i32) -> ConfigBuilder {
+        self.epochs = epochs;
+        self
+    }
+
+    // in ETH
+    pub fn initial_stake(mut self, initial_stake: u64) -> ConfigBuilder {
+        self.initial_stake = initial_stake;
+        self
+    }
+
+    // in ETH, sweeping the initial stake up to it, in steps of itself
+    pub fn final_stake(mut self, final_stake: u64) -> ConfigBuilder {
+        self.final_stake = Some(final_stake);
+        self
+    }
+
+    pub fn probability_online(mut self, probability: f32) -> ConfigBuilder {
+        self.probability_online = Some(probability);
+        self
+    }
+
+    pub fn probability_honest(mut self, probability: f32) -> ConfigBuilder {
+        self.probability_honest = Some(probability);
+        self
+    }
+
+    pub fn probability_inclusion(mut self, probability: f32) -> ConfigBuilder {
+        self.probability_inclusion = Some(probability);
+        self
+    }
+
+    // the fork of the reward model, from genesis on
+    pub fn reward_model(mut self, reward_model: RewardModel) -> ConfigBuilder {
+        self.reward_model = Some(reward_model);
+        self
+    }
+
+    pub fn forks(mut self, forks: ForkSchedule) -> ConfigBuilder {
+        self.forks = Some(forks);
+        self
+    }
+
+    // tiers replace the single one, and bring their own probabilities
+    pub fn tier(mut self, tier: Tier) -> ConfigBuilder {
+        self.tiers.push(tier);
+        self
+    }
+
+    pub fn tracked_validator(mut self, tracked_validator: TrackedValidator) -> ConfigBuilder {
+        self.tracked_validators.push(tracked_validator);
+        self
+    }
+
+    pub fn sweep(mut self, axis: SweepAxis) -> ConfigBuilder {
+        self.sweep.push(axis);
+        self
+    }
+
+    pub fn slashing_model(mut self, slashing_model: SlashingModel) -> ConfigBuilder {
+        self.slashing_model = slashing_model;
+        self
+    }
+
+    pub fn deposit_model(mut self, deposit_model: DepositModel) -> ConfigBuilder {
+        self.deposit_model = deposit_model;
+        self
+    }
+
+    pub fn exit_model(mut self, exit_model: ExitModel) -> ConfigBuilder {
+        self.exit_model = exit_model;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> ConfigBuilder {
+        self.seed = seed;
+        self
+    }
+
+    pub fn jobs(mut self, jobs: usize) -> ConfigBuilder {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    pub fn runs(mut self, runs: u64) -> ConfigBuilder {
+        self.runs = runs;
+        self
+    }
+
+    pub fn percentiles(mut self, percentiles: &[f64]) -> ConfigBuilder {
+        self.percentiles = percentiles.to_vec();
+        self
+    }
+
+    pub fn build(self) -> Result<Config> {
+        if self.initial_stake < 500_000 {
+            // TODO and multiple of 500,000
+            return Err(Error::config(
+                "initial_stake should be equal or greater than 500000",
+            ));
+        }
+        let total_at_stake_initial = self
+            .initial_stake
+            .checked_mul(1_000_000_000)
+            .ok_or_else(|| Error::config("initial_stake is too large to be held in Gwei"))?;
+        if self.epochs < 1 {
+            return Err(Error::config("epoch should be a positive integer"));
+        }
+        if self.report_type != "epoch" && self.report_type != "monthly" {
+            return Err(Error::invalid_value(
+                "report type",
+                &self.report_type,
+                "'epoch' or 'monthly'",
+            ));
+        }
+        // a month of a monthly report is at least an epoch long
+        let is_short = |epochs: f64| epochs < MONTHS_PER_YEAR as f64;
+        if self.report_type == "monthly"
+            && (is_short(self.epochs as f64)
+                || self.sweep.iter().any(|axis| {
+                    axis.parameter == SweepParameter::Epochs
+                        && axis.values.iter().any(|epochs| is_short(*epochs))
+                }))
+        {
+            return Err(Error::config("a monthly report needs at least 12 epochs"));
+        }
+
+        let forks = match (self.reward_model, &self.forks) {
+            (Some(_), Some(_)) => {
+                return Err(Error::config(
+                    "choose either a reward model or a fork schedule",
+                ))
+            }
+            (Some(reward_model), None) => ForkSchedule::new(Fork::from(reward_model)),
+            (None, Some(forks)) => forks.clone(),
+            (None, None) => ForkSchedule::new(Fork::Phase0),
+        };
+
+        let tiers = self.get_tiers()?;
+        for tracked_validator in self.tracked_validators.iter() {
+            if tracked_validator.balance == 0 {
+                return Err(Error::config(
+                    "tracked validator balance should be a positive number of ETH",
+                ));
+            }
+            ConfigBuilder::check_tier(&tracked_validator.tier)?;
+        }
+
+        let is_swept = |parameter| self.sweep.iter().any(|axis| axis.parameter == parameter);
+        if tiers.len() > 1
+            && (is_swept(SweepParameter::ProbabilityOnline)
+                || is_swept(SweepParameter::ProbabilityHonest))
+        {
+            return Err(Error::config(
+                "probabilities can only be swept with a single tier",
+            ));
+        }
+
+        // axes can be made out of any values, not only parsed ones
+        for axis in self.sweep.iter() {
+            for value in axis.values.iter() {
+                axis.parameter.validate(*value)?;
+            }
+        }
+
+        // the final stake sweeps the initial one, in steps of itself
+        let mut sweep = self.sweep.clone();
+        if let Some(final_stake) = self.final_stake {
+            if final_stake > 10_000_000 {
+                return Err(Error::config("final_stake should be less than 10 000 000"));
+            }
+            if is_swept(SweepParameter::InitialStake) {
+                return Err(Error::config(
+                    "choose either a final stake or an initial stake sweep",
+                ));
+            }
+
+            sweep.insert(
+                0,
+                SweepAxis::range(
+                    SweepParameter::InitialStake,
+                    self.initial_stake as f64,
+                    final_stake as f64,
+                    self.initial_stake as f64,
+                )?,
+            );
+        }
+
+        match self.slashing_model {
+            SlashingModel::Probability(probability) => {
+                Config::check_probability("slashing probability", probability)?;
+            }
+            SlashingModel::None | SlashingModel::Schedule(_) => {}
+        }
+        match self.deposit_model {
+            DepositModel::Constant(rate) | DepositModel::Poisson(rate) if rate < 0.0 => {
+                return Err(Error::config(
+                    "deposit rate should be a positive number of ETH",
+                ));
+            }
+            _ => {}
+        }
+        match self.exit_model {
+            ExitModel::Probability(probability) => {
+                Config::check_probability("exit probability", probability)?;
+            }
+            ExitModel::None | ExitModel::AprThreshold(_) | ExitModel::Schedule(_) => {}
+        }
+
+        let jobs = self
+            .jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |jobs| jobs.get()));
+        if jobs < 1 {
+            return Err(Error::config("jobs should be a positive integer"));
+        }
+        if self.runs < 1 {
+            return Err(Error::config("runs should be a positive integer"));
+        }
+        if self
+            .percentiles
+            .iter()
+            .any(|percentile| !(0.0..=100.0).contains(percentile))
+        {
+            return Err(Error::config(
+                "percentiles should be in the interval [0,100]",
+            ));
+        }
+
+        Ok(Config {
+            report_type: self.report_type,
+            output_file_name: self.output_file_name,
+            output_format: self.output_format,
+            epochs: self.epochs,
+            total_at_stake_initial,
+            sweep,
+            simulation_id: 0,
+            label: String::new(),
+            jobs,
+            runs: self.runs,
+            run: 0,
+            percentiles: self.percentiles,
+            seed: self.seed,
+            forks,
+            tiers,
+            tracked_validators: self.tracked_validators,
+            slashing_model: self.slashing_model,
+            deposit_model: self.deposit_model,
+            exit_model: self.exit_model,
+        })
+    }
+
+    // the tiers given, or a single one out of the probabilities
+    fn get_tiers(&self) -> Result<Vec<Tier>> {
+        if self.tiers.is_empty() {
+            let tier = Tier::new(
+                "all",
+                1.0,
+                Config::check_probability(
+                    "probability online",
+                    self.probability_online.unwrap_or(0.99),
+                )?,
+                Config::check_probability(
+                    "probability honest",
+                    self.probability_honest.unwrap_or(1.0),
+                )?,
+                Config::check_probability(
+                    "probability inclusion",
+                    self.probability_inclusion.unwrap_or(0.99),
+                )?,
+            );
+            return Ok(vec![tier]);
+        }
+
+        if self.probability_online.is_some() {
+            return Err(Error::config(
+                "the online probability is given by each tier",
+            ));
+        }
+        if self.probability_honest.is_some() || self.probability_inclusion.is_some() {
+            return Err(Error::config(
+                "the honest and inclusion probabilities are given by each tier",
+            ));
+        }
+
+        for tier in self.tiers.iter() {
+            Config::check_probability("tier share", tier.stake_share)?;
+            ConfigBuilder::check_tier(tier)?;
+        }
+        let total_share: f32 = self.tiers.iter().map(|tier| tier.stake_share).sum();
+        if (total_share - 1.0).abs() > 0.001 {
+            return Err(Error::config("the shares of the tiers should add up to 1"));
+        }
+
+        Ok(self.tiers.clone())
+    }
+
+    fn check_tier(tier: &Tier) -> Result<()> {
+        Config::check_probability("probability online", tier.probability_online)?;
+        Config::check_probability("probability honest", tier.probability_honest)?;
+        Config::check_probability("probability inclusion", tier.probability_inclusion)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        let config = ConfigBuilder::new().build().unwrap();
+
+        assert_eq!(10, config.epochs);
+        assert_eq!(500_000_000_000_000, config.total_at_stake_initial);
+        assert_eq!(0, config.seed);
+        assert_eq!(1, config.tiers.len());
+        assert_eq!(0.99, config.tiers[0].probability_online);
+        assert_eq!(1.0, config.tiers[0].probability_honest);
+        assert!(config.jobs >= 1);
+    }
+
+    #[test]
+    fn build_scenario() {
+        let config = Config::builder()
+            .epochs(100)
+            .initial_stake(1_000_000)
+            .final_stake(2_000_000)
+            .tier(Tier::new("pro", 0.75, 1.0, 1.0, 1.0))
+            .tier(Tier::new("hobbyist", 0.25, 0.9, 1.0, 0.9))
+            .tracked_validator(TrackedValidator::new(
+                "mine",
+                32_000_000_000,
+                0.95,
+                1.0,
+                1.0,
+            ))
+            .deposit_model(DepositModel::Poisson(1000.0))
+            .runs(3)
+            .seed(42)
+            .build()
+            .unwrap();
+
+        assert_eq!(100, config.epochs);
+        assert_eq!(2, config.tiers.len());
+        assert_eq!(1, config.tracked_validators.len());
+        assert_eq!(vec![1_000_000.0, 2_000_000.0], config.sweep[0].values);
+        assert_eq!(6, config.get_configurations().len());
+    }
+
+    #[test]
+    fn build_invalid() {
+        assert!(Config::builder().epochs(0).build().is_err());
+        assert!(Config::builder().initial_stake(1000).build().is_err());
+        assert!(Config::builder()
+            .initial_stake(100_000_000_000)
+            .build()
+            .is_err());
+        assert!(Config::builder()
+            .sweep(SweepAxis::range(SweepParameter::InitialStake, 1e6, 1e11, 1e10).unwrap())
+            .build()
+            .is_err());
+        assert!(Config::builder().probability_online(1.5).build().is_err());
+        assert!(Config::builder().report_type("daily").build().is_err());
+        assert!(Config::builder().report_type("monthly").build().is_err());
+        assert!(Config::builder()
+            .report_type("monthly")
+            .epochs(120)
+            .sweep(SweepAxis::parse("epochs=10,120").unwrap())
+            .build()
+            .is_err());
+        assert!(Config::builder().runs(0).build().is_err());
+        assert!(Config::builder()
+            .exit_model(ExitModel::Probability(2.0))
+            .build()
+            .is_err());
+        assert!(Config::builder()
+            .deposit_model(DepositModel::Constant(-1.0))
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn build_forks() {
+        let config = Config::builder()
+            .reward_model(RewardModel::Altair)
+            .build()
+            .unwrap();
+        assert_eq!(Fork::Altair, config.forks.get_fork(0));
+        assert_eq!(Fork::Phase0, Config::default().forks.get_fork(1000));
+
+        // a reward model is a fork of its own
+        assert!(Config::builder()
+            .reward_model(RewardModel::Altair)
+            .forks(ForkSchedule::mainnet())
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn build_tiers() {
+        // the shares add up to 1
+        assert!(Config::builder()
+            .tier(Tier::new("A", 0.5, 1.0, 1.0, 1.0))
+            .build()
+            .is_err());
+
+        // and they bring their own probabilities
+        assert!(Config::builder()
+            .tier(Tier::new("A", 1.0, 1.0, 1.0, 1.0))
+            .probability_honest(0.9)
+            .build()
+            .is_err());
+
+        // which can't be swept along with other tiers
+        assert!(Config::builder()
+            .tier(Tier::new("A", 0.5, 1.0, 1.0, 1.0))
+            .tier(Tier::new("B", 0.5, 1.0, 1.0, 1.0))
+            .sweep(SweepAxis::parse("probability-online=0.5,1").unwrap())
+            .build()
+            .is_err());
+    }
+}
@@ -6,11 +6,29 @@
 
 use std::fmt;
 
+#[derive(Copy, Clone, Debug)]
 pub struct Deltas {
     pub head_ffg_reward: u64,
     pub head_ffg_penalty: u64,
     pub proposer_reward: u64,
     pub attester_reward: u64,
+    pub inactivity_penalty: u64,
+
+    // slashings
+    pub slashing_penalty: u64,
+    pub midway_penalty: u64,
+    pub whistleblower_reward: u64,
+    pub slashing_proposer_reward: u64,
+
+    // sync committees (altair)
+    pub sync_committee_reward: u64,
+    pub sync_committee_penalty: u64,
+}
+
+impl Default for Deltas {
+    fn default() -> Deltas {
+        Deltas::new()
+    }
 }
 
 impl Deltas {
@@ -20,16 +38,50 @@ impl Deltas {
             head_ffg_penalty: 0,
             proposer_reward: 0,
             attester_reward: 0,
+            inactivity_penalty: 0,
+            slashing_penalty: 0,
+            midway_penalty: 0,
+            whistleblower_reward: 0,
+            slashing_proposer_reward: 0,
+            sync_committee_reward: 0,
+            sync_committee_penalty: 0,
         }
     }
+
+    pub fn get_total_rewards(&self) -> u64 {
+        self.head_ffg_reward
+            + self.proposer_reward
+            + self.attester_reward
+            + self.whistleblower_reward
+            + self.slashing_proposer_reward
+            + self.sync_committee_reward
+    }
+
+    pub fn get_total_penalties(&self) -> u64 {
+        self.head_ffg_penalty
+            + self.inactivity_penalty
+            + self.slashing_penalty
+            + self.midway_penalty
+            + self.sync_committee_penalty
+    }
 }
 
 impl fmt::Display for Deltas {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "{};{};{};{};",
-            self.head_ffg_reward, self.head_ffg_penalty, self.proposer_reward, self.attester_reward,
+            "{};{};{};{};{};{};{};{};{};{};{};",
+            self.head_ffg_reward,
+            self.head_ffg_penalty,
+            self.proposer_reward,
+            self.attester_reward,
+            self.inactivity_penalty,
+            self.slashing_penalty,
+            self.midway_penalty,
+            self.whistleblower_reward,
+            self.slashing_proposer_reward,
+            self.sync_committee_reward,
+            self.sync_committee_penalty,
         )
     }
 }
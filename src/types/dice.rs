@@ -4,35 +4,61 @@
 //
 ////////////////////////////////////////////////////////////////////////////////
 
+use super::config;
+use super::config::{ExitModel, SlashingModel};
+use super::fork::ForkParameters;
 use super::state::State;
-use rand::rngs::ThreadRng;
-use rand::Rng;
+use crate::error::{Error, Result};
+use rand::distributions::{Distribution, Poisson};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 pub struct Dice {
-    rng: ThreadRng,
+    rng: StdRng,
 }
 
 impl Dice {
-    pub fn new() -> Dice {
+    pub fn new(seed: u64) -> Dice {
         Dice {
-            rng: rand::thread_rng(),
+            rng: StdRng::seed_from_u64(seed),
         }
     }
 
+    // derives an independent seed for each stream (e.g. each simulation)
+    //   out of a single master seed (SplitMix64 finalizer)
+    pub fn derive_seed(seed: u64, stream: u64) -> u64 {
+        let mut z = seed.wrapping_add((stream + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
     pub fn throw_dice(&mut self, probability: f32) -> bool {
         probability > self.rng.gen()
     }
 
-    pub fn pick_epoch_proposers(&mut self, state: &State) -> Vec<usize> {
+    pub fn pick_epoch_proposers(
+        &mut self,
+        state: &State,
+        parameters: &ForkParameters,
+    ) -> Result<Vec<usize>> {
         let mut proposer_indices = vec![];
 
         let n = state.validators.len();
         let proposers_per_epoch = 32;
-        let max_effective_balance = 32_000_000_000;
         let max_random_byte = 255;
 
-        if state.get_total_active_validators() < 32 {
-            panic!("not enough active validators");
+        // slashed validators can't propose, so we'd never find enough of them
+        let candidates = state
+            .validators
+            .iter()
+            .filter(|validator| validator.is_active && !validator.is_slashed)
+            .count();
+        if candidates < proposers_per_epoch {
+            return Err(Error::Simulation(format!(
+                "not enough active validators to propose blocks ({} out of {})",
+                candidates, proposers_per_epoch
+            )));
         }
 
         loop {
@@ -52,7 +78,7 @@ impl Dice {
             // effective balance bias on proposer election
             let random_byte = self.rng.gen_range(0, 255);
             if state.validators[candidate_index].effective_balance * max_random_byte
-                >= random_byte * max_effective_balance
+                >= random_byte * parameters.max_effective_balance
             {
                 proposer_indices.push(candidate_index);
             } else {
@@ -60,7 +86,243 @@ impl Dice {
             }
         }
 
-        proposer_indices
+        Ok(proposer_indices)
+    }
+
+    // SPEC (altair): get_next_sync_committee_indices(), active validators
+    //   (slashed or not) by the effective balance bias, with repeats
+    pub fn pick_sync_committee(
+        &mut self,
+        state: &State,
+        parameters: &ForkParameters,
+    ) -> Result<Vec<usize>> {
+        let mut sync_committee = vec![];
+
+        let n = state.validators.len();
+        let max_random_byte = 255;
+
+        if !state.validators.iter().any(|validator| validator.is_active) {
+            return Err(Error::Simulation(String::from(
+                "no active validators to pick a sync committee from",
+            )));
+        }
+
+        while sync_committee.len() < config::SYNC_COMMITTEE_SIZE {
+            let candidate_index = self.rng.gen_range(0, n);
+            if !state.validators[candidate_index].is_active {
+                continue;
+            }
+
+            let random_byte = self.rng.gen_range(0, 255);
+            if state.validators[candidate_index].effective_balance * max_random_byte
+                >= random_byte * parameters.max_effective_balance
+            {
+                sync_committee.push(candidate_index);
+            }
+        }
+
+        Ok(sync_committee)
+    }
+
+    // a random position in [0,1)
+    pub fn throw_position(&mut self) -> f32 {
+        self.rng.gen()
+    }
+
+    pub fn throw_poisson(&mut self, mean: f64) -> u64 {
+        if mean <= 0.0 {
+            return 0;
+        }
+
+        Poisson::new(mean).sample(&mut self.rng)
+    }
+
+    pub fn pick_one(&mut self, indices: &[usize]) -> usize {
+        indices[self.rng.gen_range(0, indices.len())]
+    }
+
+    pub fn pick_slashed_validators(&mut self, state: &State, epoch_id: i32) -> Vec<usize> {
+        // slashed validators can't be slashed again
+        let is_candidate = |index: &usize| {
+            state.validators[*index].is_active && !state.validators[*index].is_slashed
+        };
+
+        match &state.config.slashing_model {
+            SlashingModel::None => vec![],
+            SlashingModel::Probability(probability) => (0..state.validators.len())
+                .filter(is_candidate)
+                .filter(|_| self.throw_dice(*probability))
+                .collect(),
+            SlashingModel::Schedule(schedule) => {
+                let amount = Dice::get_scheduled_amount(schedule, epoch_id);
+                if amount == 0 {
+                    return vec![];
+                }
+
+                let candidates: Vec<usize> =
+                    (0..state.validators.len()).filter(is_candidate).collect();
+                self.pick_many(&candidates, amount)
+            }
+        }
+    }
+
+    pub fn pick_exiting_validators(&mut self, state: &State, epoch_id: i32) -> Vec<usize> {
+        // SPEC: process_voluntary_exit() conditions
+        let is_candidate = |index: &usize| {
+            let validator = &state.validators[*index];
+
+            validator.is_active_validator(epoch_id)
+                && validator.exit_epoch == config::FAR_FUTURE_EPOCH
+                && epoch_id >= validator.activation_epoch + config::PERSISTENT_COMMITTEE_PERIOD
+        };
+
+        match &state.config.exit_model {
+            ExitModel::None => vec![],
+            ExitModel::Probability(probability) => (0..state.validators.len())
+                .filter(is_candidate)
+                .filter(|_| self.throw_dice(*probability))
+                .collect(),
+            ExitModel::AprThreshold(threshold) => (0..state.validators.len())
+                .filter(is_candidate)
+                .filter(|index| state.validators[*index].get_apr(epoch_id) < *threshold)
+                .collect(),
+            ExitModel::Schedule(schedule) => {
+                let amount = Dice::get_scheduled_amount(schedule, epoch_id);
+                if amount == 0 {
+                    return vec![];
+                }
+
+                let candidates: Vec<usize> =
+                    (0..state.validators.len()).filter(is_candidate).collect();
+                self.pick_many(&candidates, amount)
+            }
+        }
+    }
+
+    // picks up to `amount` different elements out of `indices`
+    fn pick_many(&mut self, indices: &[usize], amount: u64) -> Vec<usize> {
+        let amount = std::cmp::min(amount as usize, indices.len());
+
+        rand::seq::index::sample(&mut self.rng, indices.len(), amount)
+            .into_iter()
+            .map(|i| indices[i])
+            .collect()
+    }
+
+    fn get_scheduled_amount(schedule: &[(i32, u64)], epoch_id: i32) -> u64 {
+        schedule
+            .iter()
+            .filter(|(epoch, _)| *epoch == epoch_id)
+            .map(|(_, amount)| amount)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::fork::{ALTAIR, PHASE0};
+    use crate::types::Config;
+
+    #[test]
+    fn same_seed_same_proposers() {
+        let state = State::new(Config::default());
+
+        let mut dice_a = Dice::new(42);
+        let mut dice_b = Dice::new(42);
+
+        for _ in 0..5 {
+            assert_eq!(
+                dice_a.pick_epoch_proposers(&state, &PHASE0).unwrap(),
+                dice_b.pick_epoch_proposers(&state, &PHASE0).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn not_enough_proposers() {
+        let mut state = State::new(Config::default());
+        let mut dice = Dice::new(0);
+
+        for validator in state.validators.iter_mut().skip(31) {
+            validator.is_slashed = true;
+        }
+
+        match dice.pick_epoch_proposers(&state, &PHASE0) {
+            Err(Error::Simulation(_)) => {}
+            _ => panic!("expected a simulation error"),
+        }
+    }
+
+    #[test]
+    fn pick_sync_committee() {
+        let mut state = State::new(Config::default());
+        let mut dice = Dice::new(0);
+
+        for validator in state.validators.iter_mut().skip(100) {
+            validator.is_active = false;
+        }
+
+        let sync_committee = dice.pick_sync_committee(&state, &ALTAIR).unwrap();
+
+        // out of 100 validators, so some of them sit more than once
+        assert_eq!(config::SYNC_COMMITTEE_SIZE, sync_committee.len());
+        assert!(sync_committee.iter().all(|index| *index < 100));
+
+        for validator in state.validators.iter_mut() {
+            validator.is_active = false;
+        }
+        assert!(dice.pick_sync_committee(&state, &ALTAIR).is_err());
+    }
+
+    #[test]
+    fn pick_slashed_validators() {
+        let config = Config::builder()
+            .slashing_model(SlashingModel::Schedule(vec![(3, 10)]))
+            .build()
+            .unwrap();
+        let state = State::new(config);
+        let mut dice = Dice::new(0);
+
+        assert_eq!(0, dice.pick_slashed_validators(&state, 2).len());
+
+        let mut slashed = dice.pick_slashed_validators(&state, 3);
+        slashed.sort();
+        slashed.dedup();
+        assert_eq!(10, slashed.len());
+    }
+
+    #[test]
+    fn pick_exiting_validators() {
+        let epoch_id = config::PERSISTENT_COMMITTEE_PERIOD;
+        let config = Config::builder()
+            .exit_model(ExitModel::Schedule(vec![(epoch_id - 1, 5), (epoch_id, 5)]))
+            .build()
+            .unwrap();
+        let mut state = State::new(config);
+        let mut dice = Dice::new(0);
+
+        // too early for a voluntary exit
+        assert_eq!(0, dice.pick_exiting_validators(&state, epoch_id - 1).len());
+        assert_eq!(5, dice.pick_exiting_validators(&state, epoch_id).len());
+
+        // no validator has earned anything
+        state.config.exit_model = ExitModel::AprThreshold(1.0);
+        state.validators[0].balance += 1_000_000_000;
+        assert_eq!(
+            state.validators.len() - 1,
+            dice.pick_exiting_validators(&state, epoch_id).len()
+        );
+    }
+
+    #[test]
+    fn derive_seed() {
+        // deterministic
+        assert_eq!(Dice::derive_seed(42, 0), Dice::derive_seed(42, 0));
+
+        // different streams and different master seeds do not collide
+        assert_ne!(Dice::derive_seed(42, 0), Dice::derive_seed(42, 1));
+        assert_ne!(Dice::derive_seed(42, 0), Dice::derive_seed(43, 0));
     }
 }
 
@@ -0,0 +1,329 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Forks: the rules and parameters each epoch is processed by, and the
+//   schedule of the forks a simulation goes through
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use super::config::{Config, RewardModel};
+use super::validator::Validator;
+use crate::error::{Error, Result};
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Fork {
+    Phase0,
+    Altair,
+    Bellatrix,
+    Capella,
+    Deneb,
+    Electra,
+}
+
+// the constants that changed from one fork to the next
+#[derive(Debug, PartialEq)]
+pub struct ForkParameters {
+    pub max_effective_balance: u64,
+    pub base_reward_factor: u64,
+    pub inactivity_penalty_quotient: u64,
+    pub min_slashing_penalty_quotient: u64,
+    pub proportional_slashing_multiplier: u64,
+    pub whistleblower_reward_quotient: u64,
+
+    // validators per epoch, or Gwei per epoch with a balance churn
+    pub min_per_epoch_churn_limit: u64,
+    pub churn_limit_quotient: u64,
+    pub max_per_epoch_activation_churn_limit: u64,
+    pub max_per_epoch_exit_churn_limit: u64,
+    pub is_balance_churn: bool,
+}
+
+// the values the simulation was written against, before mainnet launched
+pub const PHASE0: ForkParameters = ForkParameters {
+    max_effective_balance: 32_000_000_000,
+    base_reward_factor: 64,
+    inactivity_penalty_quotient: 33_554_432,
+    min_slashing_penalty_quotient: 32,
+    proportional_slashing_multiplier: 3,
+    whistleblower_reward_quotient: 512,
+    min_per_epoch_churn_limit: 4,
+    churn_limit_quotient: 65_536,
+    max_per_epoch_activation_churn_limit: u64::MAX,
+    max_per_epoch_exit_churn_limit: u64::MAX,
+    is_balance_churn: false,
+};
+
+// the values mainnet launched with, lenient until altair made them stricter
+pub const PHASE0_MAINNET: ForkParameters = ForkParameters {
+    inactivity_penalty_quotient: 67_108_864,
+    min_slashing_penalty_quotient: 128,
+    proportional_slashing_multiplier: 1,
+    ..PHASE0
+};
+
+pub const ALTAIR: ForkParameters = ForkParameters {
+    inactivity_penalty_quotient: 50_331_648,
+    min_slashing_penalty_quotient: 64,
+    proportional_slashing_multiplier: 2,
+    ..PHASE0
+};
+
+pub const BELLATRIX: ForkParameters = ForkParameters {
+    inactivity_penalty_quotient: 16_777_216,
+    min_slashing_penalty_quotient: 32,
+    proportional_slashing_multiplier: 3,
+    ..ALTAIR
+};
+
+// withdrawals don't change any of them
+pub const CAPELLA: ForkParameters = BELLATRIX;
+
+// EIP-7514: activations capped at 8 validators per epoch
+pub const DENEB: ForkParameters = ForkParameters {
+    max_per_epoch_activation_churn_limit: 8,
+    ..CAPELLA
+};
+
+// EIP-7251: the churn goes by balance, 128 to 256 ETH per epoch. only
+//   compounding validators get a 2048 ETH effective balance, and we
+//   don't simulate their withdrawal credentials
+pub const ELECTRA: ForkParameters = ForkParameters {
+    min_slashing_penalty_quotient: 4096,
+    whistleblower_reward_quotient: 4096,
+    min_per_epoch_churn_limit: 128_000_000_000,
+    max_per_epoch_activation_churn_limit: 256_000_000_000,
+    max_per_epoch_exit_churn_limit: 256_000_000_000,
+    is_balance_churn: true,
+    ..DENEB
+};
+
+impl Fork {
+    pub fn parse(fork: &str) -> Result<Fork> {
+        match fork {
+            "phase0" => Ok(Fork::Phase0),
+            "altair" => Ok(Fork::Altair),
+            "bellatrix" => Ok(Fork::Bellatrix),
+            "capella" => Ok(Fork::Capella),
+            "deneb" => Ok(Fork::Deneb),
+            "electra" => Ok(Fork::Electra),
+            _ => Err(Error::invalid_value(
+                "fork",
+                fork,
+                "phase0, altair, bellatrix, capella, deneb or electra",
+            )),
+        }
+    }
+
+    pub fn get_parameters(&self) -> &'static ForkParameters {
+        match self {
+            Fork::Phase0 => &PHASE0,
+            Fork::Altair => &ALTAIR,
+            Fork::Bellatrix => &BELLATRIX,
+            Fork::Capella => &CAPELLA,
+            Fork::Deneb => &DENEB,
+            Fork::Electra => &ELECTRA,
+        }
+    }
+
+    // participation flags and sync committees from altair on
+    pub fn get_reward_model(&self) -> RewardModel {
+        match self {
+            Fork::Phase0 => RewardModel::Phase0,
+            _ => RewardModel::Altair,
+        }
+    }
+}
+
+impl From<RewardModel> for Fork {
+    fn from(reward_model: RewardModel) -> Fork {
+        match reward_model {
+            RewardModel::Phase0 => Fork::Phase0,
+            RewardModel::Altair => Fork::Altair,
+        }
+    }
+}
+
+impl fmt::Display for Fork {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fork = match self {
+            Fork::Phase0 => "phase0",
+            Fork::Altair => "altair",
+            Fork::Bellatrix => "bellatrix",
+            Fork::Capella => "capella",
+            Fork::Deneb => "deneb",
+            Fork::Electra => "electra",
+        };
+
+        write!(f, "{}", fork)
+    }
+}
+
+impl ForkParameters {
+    // what activating (or exiting) a validator takes out of the churn
+    pub fn get_churn(&self, validator: &Validator) -> u64 {
+        if self.is_balance_churn {
+            validator.effective_balance
+        } else {
+            1
+        }
+    }
+}
+
+// the epochs forks activate at, each with its parameter set (the one of
+//   the fork, unless told otherwise). phase0 until the first one
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForkSchedule {
+    forks: Vec<(i32, Fork, &'static ForkParameters)>,
+}
+
+impl ForkSchedule {
+    // a single fork from genesis on
+    pub fn new(fork: Fork) -> ForkSchedule {
+        ForkSchedule {
+            forks: vec![(0, fork, fork.get_parameters())],
+        }
+    }
+
+    pub fn from_epochs(forks: Vec<(i32, Fork)>) -> Result<ForkSchedule> {
+        if forks.iter().any(|(epoch, _)| *epoch < 0) {
+            return Err(Error::config("fork epochs should be positive integers"));
+        }
+        if forks
+            .windows(2)
+            .any(|pair| pair[0].0 >= pair[1].0 || pair[0].1 >= pair[1].1)
+        {
+            return Err(Error::config(
+                "forks should follow each other in order, each at a later epoch",
+            ));
+        }
+
+        Ok(ForkSchedule {
+            forks: forks
+                .into_iter()
+                .map(|(epoch, fork)| (epoch, fork, fork.get_parameters()))
+                .collect(),
+        })
+    }
+
+    // the forks of mainnet, at their epochs, and its phase0 launch values
+    pub fn mainnet() -> ForkSchedule {
+        ForkSchedule {
+            forks: vec![
+                (0, Fork::Phase0, &PHASE0_MAINNET),
+                (74_240, Fork::Altair, &ALTAIR),
+                (144_896, Fork::Bellatrix, &BELLATRIX),
+                (194_048, Fork::Capella, &CAPELLA),
+                (269_568, Fork::Deneb, &DENEB),
+                (364_032, Fork::Electra, &ELECTRA),
+            ],
+        }
+    }
+
+    // parses "epoch:fork,epoch:fork,..." or "mainnet"
+    pub fn parse(schedule: &str) -> Result<ForkSchedule> {
+        if schedule.trim() == "mainnet" {
+            return Ok(ForkSchedule::mainnet());
+        }
+
+        let forks = schedule
+            .split(',')
+            .map(|entry| {
+                let mut parts = entry.split(':');
+
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(epoch), Some(fork), None) => Ok((
+                        Config::parse_value("fork epoch", epoch, "a positive integer")?,
+                        Fork::parse(fork.trim())?,
+                    )),
+                    _ => Err(Error::invalid_value(
+                        "fork schedule entry",
+                        entry,
+                        "epoch:fork",
+                    )),
+                }
+            })
+            .collect::<Result<_>>()?;
+
+        ForkSchedule::from_epochs(forks)
+    }
+
+    pub fn get_fork(&self, epoch_id: i32) -> Fork {
+        self.get_entry(epoch_id)
+            .map_or(Fork::Phase0, |(_, fork, _)| *fork)
+    }
+
+    pub fn get_parameters(&self, epoch_id: i32) -> &'static ForkParameters {
+        self.get_entry(epoch_id)
+            .map_or(&PHASE0, |(_, _, parameters)| *parameters)
+    }
+
+    fn get_entry(&self, epoch_id: i32) -> Option<&(i32, Fork, &'static ForkParameters)> {
+        self.forks
+            .iter()
+            .rev()
+            .find(|(epoch, _, _)| *epoch <= epoch_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_fork() {
+        let forks = ForkSchedule::parse("10:altair, 20:electra").unwrap();
+
+        assert_eq!(Fork::Phase0, forks.get_fork(0));
+        assert_eq!(Fork::Altair, forks.get_fork(10));
+        assert_eq!(Fork::Altair, forks.get_fork(19));
+        assert_eq!(Fork::Electra, forks.get_fork(1000));
+        assert_eq!(Fork::Capella, ForkSchedule::mainnet().get_fork(200_000));
+    }
+
+    #[test]
+    fn get_parameters() {
+        let mainnet = ForkSchedule::mainnet();
+
+        assert_eq!(&PHASE0_MAINNET, mainnet.get_parameters(0));
+        assert_eq!(&ALTAIR, mainnet.get_parameters(74_240));
+        assert_eq!(&PHASE0, ForkSchedule::new(Fork::Phase0).get_parameters(0));
+        assert_eq!(&PHASE0, ForkSchedule::new(Fork::Altair).get_parameters(-1));
+    }
+
+    #[test]
+    fn parse_invalid_schedule() {
+        assert!(ForkSchedule::parse("10:altair:20").is_err());
+        assert!(ForkSchedule::parse("10:shanghai").is_err());
+        assert!(ForkSchedule::parse("-1:altair").is_err());
+
+        // out of order
+        assert!(ForkSchedule::parse("20:altair,10:bellatrix").is_err());
+        assert!(ForkSchedule::parse("10:bellatrix,20:altair").is_err());
+        assert!(ForkSchedule::parse("10:altair,10:bellatrix").is_err());
+    }
+
+    #[test]
+    fn parameters() {
+        assert_eq!(
+            PHASE0.max_effective_balance,
+            Fork::Electra.get_parameters().max_effective_balance
+        );
+        assert_eq!(
+            16_777_216,
+            Fork::Capella.get_parameters().inactivity_penalty_quotient
+        );
+        assert_eq!(
+            8,
+            Fork::Deneb
+                .get_parameters()
+                .max_per_epoch_activation_churn_limit
+        );
+        assert_eq!(RewardModel::Altair, Fork::Deneb.get_reward_model());
+
+        let validator = Validator::new(32_000_000_000);
+        assert_eq!(1, PHASE0.get_churn(&validator));
+        assert_eq!(32_000_000_000, ELECTRA.get_churn(&validator));
+    }
+}
@@ -5,15 +5,25 @@
 ////////////////////////////////////////////////////////////////////////////////
 
 pub mod config;
+pub mod config_builder;
 pub mod deltas;
 pub mod dice;
+pub mod fork;
 pub mod output;
+pub mod scenario;
+pub mod slashing;
 pub mod state;
+pub mod summary;
+pub mod sweep;
 pub mod validator;
 
 pub use config::*;
+pub use config_builder::*;
 pub use deltas::*;
 pub use dice::*;
+pub use fork::*;
 pub use output::*;
+pub use slashing::*;
 pub use state::*;
+pub use summary::*;
 pub use validator::*;
@@ -5,75 +5,270 @@
 ////////////////////////////////////////////////////////////////////////////////
 use super::config::*;
 use super::deltas::Deltas;
+use super::fork::Fork;
+use super::summary::SummaryRow;
+use super::validator::{Validator, ValidatorStatus};
+use crate::error::{Error, Result};
+use crate::exporter::get_exporter;
 use serde::{Serialize};
+use std::io::{self, Write};
+use std::sync::mpsc::SyncSender;
+
+// what a simulation hands over to whoever writes the reports
+pub enum Report {
+    EpochRow(EpochReportRow),
+    MonthlyRow(MonthlyReportRow),
+    TierRow(TierReportRow),
+    MonthlyTierRow(MonthlyTierReportRow),
+    // the simulation is over, with whatever it kept in memory
+    Finished(Box<Config>, Box<Output>),
+    // the simulation couldn't go on
+    Failed(Error),
+}
+
+// report rows sent away as each epoch completes, instead of kept in memory
+pub struct OutputStream {
+    config: Config,
+    sender: SyncSender<Report>,
+    monthly_tiers: MonthlyTierAggregator,
+}
+
+impl OutputStream {
+    // nobody listens anymore once the run failed, so rows can be dropped
+    fn push(&self, row: EpochReportRow) {
+        if self.config.report_type == "monthly" {
+            if let Some(month_number) = get_month_number(row.epoch_id, &self.config) {
+                let _ = self
+                    .sender
+                    .send(Report::MonthlyRow(Output::get_monthly_report_row(
+                        &row,
+                        month_number,
+                        &self.config,
+                    )));
+            }
+        } else {
+            let _ = self.sender.send(Report::EpochRow(row));
+        }
+    }
+
+    // the breakdown only makes sense with several tiers
+    fn push_tier_rows(&mut self, rows: Vec<TierReportRow>) {
+        if self.config.tiers.len() < 2 {
+            return;
+        }
 
-const MONTHS_PER_YEAR: i32 = 12;
+        if self.config.report_type == "monthly" {
+            for row in self.monthly_tiers.push(&rows, &self.config) {
+                let _ = self.sender.send(Report::MonthlyTierRow(row));
+            }
+        } else {
+            for row in rows {
+                let _ = self.sender.send(Report::TierRow(row));
+            }
+        }
+    }
+}
+
+// the month an epoch closes, if any
+fn get_month_number(epoch_id: i32, config: &Config) -> Option<u32> {
+    let epochs_per_month = config.epochs / MONTHS_PER_YEAR;
+    if epochs_per_month == 0 || epoch_id == 0 || epoch_id % epochs_per_month != 0 {
+        return None;
+    }
+
+    Some((epoch_id / epochs_per_month) as u32)
+}
+
+// sums the tier rows of the epochs of a month, into a row per tier once the
+//   month is over
+#[derive(Default)]
+struct MonthlyTierAggregator {
+    rows: Vec<MonthlyTierReportRow>,
+}
+
+impl MonthlyTierAggregator {
+    // the tier rows of an epoch, one per tier
+    fn push(&mut self, rows: &[TierReportRow], config: &Config) -> Vec<MonthlyTierReportRow> {
+        for row in rows {
+            if self.rows.len() <= row.tier_index {
+                self.rows.resize(row.tier_index + 1, MonthlyTierReportRow::default());
+            }
+            self.rows[row.tier_index].aggregate(row);
+        }
+
+        match rows.first().and_then(|row| get_month_number(row.epoch_id, config)) {
+            Some(month_number) => self
+                .rows
+                .drain(..)
+                .map(|mut row| {
+                    row.month_number = month_number;
+                    row
+                })
+                .collect(),
+            None => vec![],
+        }
+    }
+}
 
 pub struct Output {
     pub rows: Vec<EpochReportRow>,
+    pub tier_rows: Vec<TierReportRow>,
+    pub trace_rows: Vec<ValidatorTraceRow>,
+
+    stream: Option<OutputStream>,
+}
+
+impl Default for Output {
+    fn default() -> Output {
+        Output::new()
+    }
 }
 
 impl Output {
     pub fn new() -> Output {
         let rows = vec![];
+        let tier_rows = vec![];
+        let trace_rows = vec![];
+
+        Output {
+            rows,
+            tier_rows,
+            trace_rows,
+            stream: None,
+        }
+    }
 
-        Output { rows: rows }
+    // epoch rows (or monthly ones) and tier rows are streamed to the
+    //   sender. only the traces stay in memory
+    pub fn streaming(config: &Config, sender: SyncSender<Report>) -> Output {
+        Output {
+            stream: Some(OutputStream {
+                config: config.clone(),
+                sender,
+                monthly_tiers: MonthlyTierAggregator::default(),
+            }),
+            ..Output::new()
+        }
     }
 
     pub fn push(&mut self, row: EpochReportRow) {
-        self.rows.push(row);
-    }
-
-    pub fn print_epoch_report(&self, config: &Config) {
-        if config.output_format == "csv" { 
-            Output::print_epoch_report_in_csv(&self.rows);
-        }
-    }
-
-    fn print_epoch_report_in_csv(data: &Vec<EpochReportRow>) {
-        println!(
-            "{},{},{},{},{},{},{},{},{},{},{},{}",
-            "epoch number".to_string(),
-            "FFG rewards".to_string(),
-            "FFG penalties".to_string(),
-            "proposer rewards".to_string(),
-            "attester rewards".to_string(),
-            "total staked balance".to_string(),
-            "total effective balance".to_string(),
-            "max balance".to_string(),
-            "min balance".to_string(),
-            "total validators".to_string(),
-            "total active validatos".to_string(),
-            "time μs".to_string(),
-        );
+        match &self.stream {
+            Some(stream) => stream.push(row),
+            None => self.rows.push(row),
+        }
+    }
 
-        for row in data {
-            println!(
-                "{},{},{},{},{},{},{},{},{},{},{},{}",
-                row.epoch_id,
-                row.deltas_head_ffg_rewards,
-                row.deltas_head_ffg_penalties,
-                row.deltas_proposer_rewards,
-                row.deltas_attester_rewards,
-                row.total_staked_balance,
-                row.total_effective_balance,
-                row.max_balance,
-                row.min_balance,
-                row.total_validators,
-                row.total_active_validators,
-                row.time_elapsed,
-            );
+    pub fn push_tier_rows(&mut self, rows: Vec<TierReportRow>) {
+        match self.stream.as_mut() {
+            Some(stream) => stream.push_tier_rows(rows),
+            None => self.tier_rows.extend(rows),
         }
     }
 
-    pub fn print_monthly_report(&self, config: &Config) {
-        let monthly_report = Output::get_monthly_report(&self, config);
+    // the stream is done, nothing else will be sent
+    pub fn close_stream(&mut self) {
+        self.stream = None;
+    }
+
+    pub fn push_trace_rows(&mut self, rows: Vec<ValidatorTraceRow>) {
+        self.trace_rows.extend(rows);
+    }
 
-        if config.output_format == "json" {       
-            Output::print_monthly_report_in_json(&monthly_report);
-        } else if config.output_format == "csv" {
-            Output::print_monthly_report_in_csv(&monthly_report);
+    pub fn print_validator_trace(&self, config: &Config) -> Result<()> {
+        Output::print_rows(&self.trace_rows, &config.output_format)
+    }
+
+    pub fn print_epoch_report(&self, config: &Config) -> Result<()> {
+        // the breakdown only makes sense with several tiers
+        if config.tiers.len() < 2 {
+            return Output::print_rows(&self.rows, &config.output_format);
         }
+
+        let document = EpochReport {
+            epochs: &self.rows,
+            tiers: &self.tier_rows,
+        };
+        Output::print_rows_with_tiers(&self.rows, &self.tier_rows, &document, &config.output_format)
+    }
+
+    // the rows of a report, followed by the breakdown of its tiers
+    fn print_rows_with_tiers<R, T, D>(
+        rows: &[R],
+        tier_rows: &[T],
+        document: &D,
+        output_format: &OutputFormat,
+    ) -> Result<()>
+    where
+        R: Serialize + Default,
+        T: Serialize + Default,
+        D: Serialize,
+    {
+        match output_format {
+            // a single document, rather than two arrays one after the other
+            OutputFormat::Json => {
+                let stdout = io::stdout();
+                let mut writer = stdout.lock();
+
+                serde_json::to_writer(&mut writer, document)
+                    .map_err(io::Error::from)
+                    .and_then(|_| writeln!(writer))
+                    .map_err(|why| Error::export("the standard output", why))
+            }
+            // one table after the other, separated by an empty line
+            OutputFormat::Csv => {
+                Output::print_rows(rows, output_format)?;
+                println!();
+                Output::print_rows(tier_rows, output_format)
+            }
+            // rows of either kind, one per line
+            OutputFormat::Ndjson => {
+                Output::print_rows(rows, output_format)?;
+                Output::print_rows(tier_rows, output_format)
+            }
+        }
+    }
+
+    // statistics of the runs of a simulation, in any format
+    pub fn print_summary(rows: &[SummaryRow], config: &Config) -> Result<()> {
+        Output::print_rows(rows, &config.output_format)
+    }
+
+    // every format goes through its exporter
+    fn print_rows<T: Serialize + Default>(rows: &[T], output_format: &OutputFormat) -> Result<()> {
+        let stdout = io::stdout();
+
+        get_exporter(output_format)
+            .export(rows, &mut stdout.lock())
+            .map_err(|why| Error::export("the standard output", why))
+    }
+
+    pub fn print_monthly_report(&self, config: &Config) -> Result<()> {
+        let monthly_report = self.get_monthly_report(config);
+        if config.tiers.len() < 2 {
+            return Output::print_rows(&monthly_report, &config.output_format);
+        }
+
+        let monthly_tier_report = self.get_monthly_tier_report(config);
+        let document = MonthlyReport {
+            months: &monthly_report,
+            tiers: &monthly_tier_report,
+        };
+        Output::print_rows_with_tiers(
+            &monthly_report,
+            &monthly_tier_report,
+            &document,
+            &config.output_format,
+        )
+    }
+
+    // the tier rows of each month, as they would have been streamed
+    pub fn get_monthly_tier_report(&self, config: &Config) -> Vec<MonthlyTierReportRow> {
+        let mut aggregator = MonthlyTierAggregator::default();
+
+        self.tier_rows
+            .chunks(config.tiers.len().max(1))
+            .flat_map(|rows| aggregator.push(rows, config))
+            .collect()
     }
 
     pub fn get_monthly_report(&self, config: &Config) ->  Vec<MonthlyReportRow> {
@@ -88,49 +283,47 @@ impl Output {
         }
 
         for (index, item) in items_to_get.iter().enumerate() {
-            let current_item = &self.rows[*item as usize];
-            let network_percentage_rewards = Output::get_variation_percentage(
-                current_item.total_staked_balance,
-                config.total_at_stake_initial,
-            );
-            let network_percentage_penalties = Output::get_penalties_variation_percentage(
-                current_item.deltas_head_ffg_penalties,
-                config.total_at_stake_initial,
-            );
-            let network_percentage_net_rewards =
-                network_percentage_rewards - network_percentage_penalties;
-
-            monthly_report.push(MonthlyReportRow {
-                month_number: index as u32 + 1u32,
-                initial_staked_balance: config.total_at_stake_initial / 1_000_000_000,
-                network_percentage_rewards: network_percentage_rewards,
-                network_percentage_penalties: network_percentage_penalties,
-                network_percentage_net_rewards: network_percentage_net_rewards,
-            });
+            monthly_report.push(Output::get_monthly_report_row(
+                &self.rows[*item as usize],
+                index as u32 + 1u32,
+                config,
+            ));
         }
 
         monthly_report
     }
 
-    fn print_monthly_report_in_csv(data: &Vec<MonthlyReportRow>) {
-        for record in data {
-            println!(
-                "Month number: {}, Total Network Rewards: {} %, Total Network Penaltes: {} %, Total Net Rewards: {} %",
-                record.month_number, 
-                record.network_percentage_rewards,
-                record.network_percentage_penalties,
-                record.network_percentage_net_rewards
-            );
+    fn get_monthly_report_row(
+        current_item: &EpochReportRow,
+        month_number: u32,
+        config: &Config,
+    ) -> MonthlyReportRow {
+        // deposits are not rewards
+        let network_percentage_rewards = Output::get_variation_percentage(
+            current_item
+                .total_staked_balance
+                .saturating_sub(current_item.total_deposited_balance),
+            config.total_at_stake_initial,
+        );
+        let network_percentage_penalties = Output::get_penalties_variation_percentage(
+            current_item.get_total_penalties(),
+            config.total_at_stake_initial,
+        );
+        let network_percentage_net_rewards =
+            network_percentage_rewards - network_percentage_penalties;
+
+        MonthlyReportRow {
+            simulation_id: config.simulation_id,
+            run: config.run,
+            parameters: config.label.clone(),
+            month_number,
+            initial_staked_balance: config.total_at_stake_initial / 1_000_000_000,
+            network_percentage_rewards,
+            network_percentage_penalties,
+            network_percentage_net_rewards,
         }
     }
 
-    fn print_monthly_report_in_json(data: &Vec<MonthlyReportRow>) {
-        let json_data = serde_json::to_string(&data)
-            .expect("Couldn't convert to JSON");
-
-        println!("{}", json_data);
-    }
-
     fn get_variation_percentage(new_value: u64, old_value: u64) -> f64 {
         ((new_value as f64 - old_value as f64) / old_value as f64) * 100f64
     }
@@ -140,8 +333,27 @@ impl Output {
     }
 }
 
-#[derive(Copy, Clone, Serialize)]
+// the epoch report along with the breakdown of its tiers, as a whole
+#[derive(Serialize)]
+struct EpochReport<'a> {
+    epochs: &'a [EpochReportRow],
+    tiers: &'a [TierReportRow],
+}
+
+// the monthly report along with the breakdown of its tiers, as a whole
+#[derive(Serialize)]
+struct MonthlyReport<'a> {
+    months: &'a [MonthlyReportRow],
+    tiers: &'a [MonthlyTierReportRow],
+}
+
+#[derive(Clone, Default, Serialize)]
 pub struct MonthlyReportRow {
+    // the simulation, its run, and its parameter values if any was swept
+    pub simulation_id: u64,
+    pub run: u64,
+    pub parameters: String,
+
     pub initial_staked_balance: u64,
     pub month_number: u32,
     pub network_percentage_rewards: f64,
@@ -149,14 +361,26 @@ pub struct MonthlyReportRow {
     pub network_percentage_net_rewards: f64,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct EpochReportRow {
+    // the simulation, its run, and its parameter values if any was swept
+    pub simulation_id: u64,
+    pub run: u64,
+    pub parameters: String,
     pub epoch_id: i32,
+    pub fork: Fork,
 
     pub deltas_head_ffg_rewards: u64,
     pub deltas_head_ffg_penalties: u64,
     pub deltas_proposer_rewards: u64,
     pub deltas_attester_rewards: u64,
+    pub deltas_inactivity_penalties: u64,
+    pub deltas_slashing_penalties: u64,
+    pub deltas_midway_penalties: u64,
+    pub deltas_whistleblower_rewards: u64,
+    pub deltas_slashing_proposer_rewards: u64,
+    pub deltas_sync_committee_rewards: u64,
+    pub deltas_sync_committee_penalties: u64,
 
     pub total_staked_balance: u64,
     pub total_effective_balance: u64,
@@ -164,19 +388,45 @@ pub struct EpochReportRow {
     pub min_balance: u64,
     pub total_validators: u64,
     pub total_active_validators: u64,
+    pub deposits: u64,
+    pub total_deposited_balance: u64,
+    pub activation_queue_length: u64,
+    pub voluntary_exits: u64,
+    pub exit_queue_length: u64,
+    pub total_slashed_validators: u64,
+
+    pub finalized_epoch: i32,
+    pub finality_delay: u64,
 
     pub time_elapsed: u128,
 }
 
+impl Default for EpochReportRow {
+    fn default() -> EpochReportRow {
+        EpochReportRow::new()
+    }
+}
+
 impl EpochReportRow {
     pub fn new() -> EpochReportRow {
         EpochReportRow {
+            simulation_id: 0,
+            run: 0,
+            parameters: String::new(),
             epoch_id: 0,
+            fork: Fork::Phase0,
 
             deltas_head_ffg_rewards: 0,
             deltas_head_ffg_penalties: 0,
             deltas_proposer_rewards: 0,
             deltas_attester_rewards: 0,
+            deltas_inactivity_penalties: 0,
+            deltas_slashing_penalties: 0,
+            deltas_midway_penalties: 0,
+            deltas_whistleblower_rewards: 0,
+            deltas_slashing_proposer_rewards: 0,
+            deltas_sync_committee_rewards: 0,
+            deltas_sync_committee_penalties: 0,
 
             total_staked_balance: 0,
             total_effective_balance: 0,
@@ -184,16 +434,238 @@ impl EpochReportRow {
             min_balance: 0,
             total_validators: 0,
             total_active_validators: 0,
+            deposits: 0,
+            total_deposited_balance: 0,
+            activation_queue_length: 0,
+            voluntary_exits: 0,
+            exit_queue_length: 0,
+            total_slashed_validators: 0,
+
+            finalized_epoch: 0,
+            finality_delay: 0,
 
             time_elapsed: 0,
         }
     }
 
+    pub fn get_total_penalties(&self) -> u64 {
+        self.deltas_head_ffg_penalties
+            + self.deltas_inactivity_penalties
+            + self.deltas_slashing_penalties
+            + self.deltas_midway_penalties
+            + self.deltas_sync_committee_penalties
+    }
+
     pub fn aggregate(&mut self, deltas: &Deltas) {
         self.deltas_head_ffg_rewards += deltas.head_ffg_reward;
         self.deltas_head_ffg_penalties += deltas.head_ffg_penalty;
         self.deltas_proposer_rewards += deltas.proposer_reward;
         self.deltas_attester_rewards += deltas.attester_reward;
+        self.deltas_inactivity_penalties += deltas.inactivity_penalty;
+        self.deltas_slashing_penalties += deltas.slashing_penalty;
+        self.deltas_midway_penalties += deltas.midway_penalty;
+        self.deltas_whistleblower_rewards += deltas.whistleblower_reward;
+        self.deltas_slashing_proposer_rewards += deltas.slashing_proposer_reward;
+        self.deltas_sync_committee_rewards += deltas.sync_committee_reward;
+        self.deltas_sync_committee_penalties += deltas.sync_committee_penalty;
+    }
+}
+
+// rewards and penalties of the validators of a tier during an epoch
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TierReportRow {
+    // the simulation, its run, and its parameter values if any was swept
+    pub simulation_id: u64,
+    pub run: u64,
+    pub parameters: String,
+    pub epoch_id: i32,
+    // name of the tier
+    pub tier: String,
+    #[serde(skip)]
+    pub tier_index: usize,
+
+    pub rewards: u64,
+    pub penalties: u64,
+
+    pub total_validators: u64,
+    pub total_staked_balance: u64,
+}
+
+impl TierReportRow {
+    pub fn new(epoch_id: i32, tier: &str, tier_index: usize) -> TierReportRow {
+        TierReportRow {
+            simulation_id: 0,
+            run: 0,
+            parameters: String::new(),
+            epoch_id,
+            tier: tier.to_string(),
+            tier_index,
+
+            rewards: 0,
+            penalties: 0,
+
+            total_validators: 0,
+            total_staked_balance: 0,
+        }
+    }
+
+    pub fn aggregate(&mut self, deltas: &Deltas) {
+        self.rewards += deltas.get_total_rewards();
+        self.penalties += deltas.get_total_penalties();
+    }
+}
+
+// rewards and penalties of the validators of a tier during a month, with
+//   the tier as it stands at its end
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct MonthlyTierReportRow {
+    // the simulation, its run, and its parameter values if any was swept
+    pub simulation_id: u64,
+    pub run: u64,
+    pub parameters: String,
+    pub month_number: u32,
+    // name of the tier
+    pub tier: String,
+
+    pub rewards: u64,
+    pub penalties: u64,
+
+    pub total_validators: u64,
+    pub total_staked_balance: u64,
+}
+
+impl MonthlyTierReportRow {
+    // the row of the tier for one more epoch of the month
+    pub fn aggregate(&mut self, row: &TierReportRow) {
+        self.simulation_id = row.simulation_id;
+        self.run = row.run;
+        self.parameters = row.parameters.clone();
+        self.tier = row.tier.clone();
+
+        self.rewards += row.rewards;
+        self.penalties += row.penalties;
+
+        self.total_validators = row.total_validators;
+        self.total_staked_balance = row.total_staked_balance;
+    }
+}
+
+// what happened to a tracked validator during an epoch
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ValidatorTraceRow {
+    // the simulation, its run, and its parameter values if any was swept
+    pub simulation_id: u64,
+    pub run: u64,
+    pub parameters: String,
+    pub epoch_id: i32,
+    // name of the tracked validator
+    pub validator: String,
+    #[serde(skip)]
+    pub validator_index: usize,
+
+    pub balance: u64,
+    pub effective_balance: u64,
+
+    pub head_ffg_reward: u64,
+    pub head_ffg_penalty: u64,
+    pub proposer_reward: u64,
+    pub attester_reward: u64,
+    pub inactivity_penalty: u64,
+    pub slashing_penalty: u64,
+    pub midway_penalty: u64,
+    pub whistleblower_reward: u64,
+    pub slashing_proposer_reward: u64,
+    pub sync_committee_reward: u64,
+    pub sync_committee_penalty: u64,
+
+    pub inactivity_score: u64,
+    pub is_proposer: bool,
+    pub status: ValidatorStatus,
+}
+
+impl ValidatorTraceRow {
+    pub fn new(
+        epoch_id: i32,
+        validator: &str,
+        validator_index: usize,
+        new_validator: &Validator,
+        deltas: &Deltas,
+        is_proposer: bool,
+    ) -> ValidatorTraceRow {
+        ValidatorTraceRow {
+            simulation_id: 0,
+            run: 0,
+            parameters: String::new(),
+            epoch_id,
+            validator: validator.to_string(),
+            validator_index,
+
+            balance: new_validator.balance,
+            effective_balance: new_validator.effective_balance,
+
+            head_ffg_reward: deltas.head_ffg_reward,
+            head_ffg_penalty: deltas.head_ffg_penalty,
+            proposer_reward: deltas.proposer_reward,
+            attester_reward: deltas.attester_reward,
+            inactivity_penalty: deltas.inactivity_penalty,
+            slashing_penalty: deltas.slashing_penalty,
+            midway_penalty: deltas.midway_penalty,
+            whistleblower_reward: deltas.whistleblower_reward,
+            slashing_proposer_reward: deltas.slashing_proposer_reward,
+            sync_committee_reward: deltas.sync_committee_reward,
+            sync_committee_penalty: deltas.sync_committee_penalty,
+
+            inactivity_score: new_validator.inactivity_score,
+            is_proposer,
+            status: ValidatorStatus::Active,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn monthly_tier_report() {
+        let config = Config {
+            epochs: 24,
+            tiers: vec![
+                Tier::new("pro", 0.5, 0.99, 1.0, 0.99),
+                Tier::new("hobbyist", 0.5, 0.9, 1.0, 0.9),
+            ],
+            ..Config::default()
+        };
+
+        let mut output = Output::new();
+        for epoch_id in 0..config.epochs {
+            let rows = config
+                .tiers
+                .iter()
+                .enumerate()
+                .map(|(index, tier)| {
+                    let mut row = TierReportRow::new(epoch_id, &tier.name, index);
+                    row.rewards = index as u64 + 1;
+                    row.total_validators = epoch_id as u64;
+                    row
+                })
+                .collect();
+            output.push_tier_rows(rows);
+        }
+
+        // two epochs a month, the first one starting with epoch 0
+        let rows = output.get_monthly_tier_report(&config);
+        assert_eq!(rows.len(), 11 * 2);
+        assert_eq!(
+            (rows[0].month_number, rows[0].tier.as_str(), rows[0].rewards),
+            (1, "pro", 3)
+        );
+        assert_eq!(
+            (rows[1].month_number, rows[1].tier.as_str(), rows[1].rewards),
+            (1, "hobbyist", 6)
+        );
+        assert_eq!((rows[2].month_number, rows[2].rewards), (2, 2));
+        assert_eq!(rows[2].total_validators, 4);
     }
 }
 
@@ -0,0 +1,388 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Scenario files: every simulation variable, in a TOML (or JSON) file
+//   that can be kept along with the results. Command line flags win over it
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use super::config::{Config, DepositModel, ExitModel, SlashingModel, Tier, TrackedValidator};
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use std::fs;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Scenario {
+    // in ETH
+    pub initial_stake: Option<u64>,
+    pub final_stake: Option<u64>,
+    pub epochs: Option<i32>,
+
+    // of the single tier, when no tiers are given
+    pub probability_online: Option<f32>,
+    pub probability_honest: Option<f32>,
+    pub probability_inclusion: Option<f32>,
+    // phase0 or altair, or the forks to go through (as in --forks)
+    pub reward_model: Option<String>,
+    pub forks: Option<String>,
+
+    #[serde(default)]
+    pub tiers: Vec<ScenarioTier>,
+    #[serde(default)]
+    pub my_validators: Vec<ScenarioTier>,
+    // as in --sweep
+    #[serde(default)]
+    pub sweep: Vec<String>,
+
+    pub slashing: Option<ScenarioSlashing>,
+    pub deposits: Option<ScenarioDeposits>,
+    pub exits: Option<ScenarioExits>,
+
+    pub seed: Option<u64>,
+    pub jobs: Option<usize>,
+    pub runs: Option<u64>,
+    pub percentiles: Option<Vec<f64>>,
+
+    pub report_type: Option<String>,
+    pub output_file_name: Option<String>,
+    pub output_format: Option<String>,
+}
+
+// a tier, or a tracked validator (with its balance in ETH instead of a share)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ScenarioTier {
+    pub name: String,
+    pub share: Option<f32>,
+    pub balance: Option<f64>,
+    pub online: f32,
+    #[serde(default = "ScenarioTier::get_default_probability")]
+    pub honest: f32,
+    // no default, as with --tier: it couldn't be that of the flags anyway
+    pub inclusion: f32,
+}
+
+// how validators get slashed, deposit or exit, each section with only
+//   its own fields. only one way at a time. schedules are
+//   "epoch:amount,epoch:amount,...", or a CSV file of them
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ScenarioSlashing {
+    pub probability: Option<f32>,
+    pub schedule: Option<String>,
+    pub schedule_file: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ScenarioDeposits {
+    // ETH deposited per day, and how (fixed, poisson)
+    pub rate: Option<f64>,
+    pub arrivals: Option<String>,
+    pub schedule: Option<String>,
+    pub schedule_file: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ScenarioExits {
+    pub probability: Option<f32>,
+    pub apr_threshold: Option<f64>,
+    pub schedule: Option<String>,
+    pub schedule_file: Option<String>,
+}
+
+impl Scenario {
+    // a .json file is read as JSON, anything else as TOML
+    pub fn read(file_name: &str) -> Result<Scenario> {
+        let contents = fs::read_to_string(file_name).map_err(|why| Error::file(file_name, why))?;
+
+        if file_name.ends_with(".json") {
+            serde_json::from_str(&contents).map_err(|why| Error::file(file_name, why))
+        } else {
+            Scenario::parse(&contents).map_err(|why| Error::file(file_name, why))
+        }
+    }
+
+    fn parse(contents: &str) -> std::result::Result<Scenario, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    pub fn get_tiers(&self) -> Result<Vec<Tier>> {
+        self.tiers
+            .iter()
+            .map(|tier| {
+                let share = tier
+                    .share
+                    .ok_or_else(|| Error::Config(format!("tier {} needs a share", tier.name)))?;
+                Config::check_probability("tier share", share)?;
+                tier.check()?;
+
+                Ok(Tier::new(
+                    &tier.name,
+                    share,
+                    tier.online,
+                    tier.honest,
+                    tier.inclusion,
+                ))
+            })
+            .collect()
+    }
+
+    pub fn get_tracked_validators(&self) -> Result<Vec<TrackedValidator>> {
+        self.my_validators
+            .iter()
+            .map(|validator| {
+                let balance = validator.balance.unwrap_or(0.0);
+                if balance <= 0.0 {
+                    return Err(Error::config(
+                        "tracked validator balance should be a positive number of ETH",
+                    ));
+                }
+                validator.check()?;
+
+                Ok(TrackedValidator::new(
+                    &validator.name,
+                    (balance * 1_000_000_000.0) as u64,
+                    validator.online,
+                    validator.honest,
+                    validator.inclusion,
+                ))
+            })
+            .collect()
+    }
+
+    pub fn get_slashing_model(&self) -> Result<SlashingModel> {
+        let slashing = match &self.slashing {
+            Some(slashing) => slashing,
+            None => return Ok(SlashingModel::None),
+        };
+
+        match (
+            slashing.probability,
+            get_schedule(&slashing.schedule, &slashing.schedule_file)?,
+        ) {
+            (Some(_), Some(_)) => Err(Error::config(
+                "choose either a slashing probability or a slashing schedule",
+            )),
+            (Some(probability), None) => Ok(SlashingModel::Probability(probability)),
+            (None, Some(schedule)) => Ok(SlashingModel::Schedule(schedule)),
+            (None, None) => Ok(SlashingModel::None),
+        }
+    }
+
+    pub fn get_deposit_model(&self) -> Result<DepositModel> {
+        let deposits = match &self.deposits {
+            Some(deposits) => deposits,
+            None => return Ok(DepositModel::None),
+        };
+
+        let is_poisson = match deposits.arrivals.as_deref().unwrap_or("fixed") {
+            "fixed" => false,
+            "poisson" => true,
+            arrivals => {
+                return Err(Error::invalid_value(
+                    "deposit arrivals",
+                    arrivals,
+                    "'fixed' or 'poisson'",
+                ))
+            }
+        };
+
+        match (
+            deposits.rate,
+            get_schedule(&deposits.schedule, &deposits.schedule_file)?,
+        ) {
+            (Some(_), Some(_)) => Err(Error::config(
+                "choose either a deposit rate or a deposit schedule",
+            )),
+            (Some(rate), None) => {
+                if is_poisson {
+                    Ok(DepositModel::Poisson(rate))
+                } else {
+                    Ok(DepositModel::Constant(rate))
+                }
+            }
+            (None, _) if is_poisson => Err(Error::config(
+                "poisson deposit arrivals need a deposit rate",
+            )),
+            (None, Some(schedule)) => Ok(DepositModel::Schedule(schedule)),
+            (None, None) => Ok(DepositModel::None),
+        }
+    }
+
+    pub fn get_exit_model(&self) -> Result<ExitModel> {
+        let exits = match &self.exits {
+            Some(exits) => exits,
+            None => return Ok(ExitModel::None),
+        };
+
+        match (
+            exits.probability,
+            exits.apr_threshold,
+            get_schedule(&exits.schedule, &exits.schedule_file)?,
+        ) {
+            (None, None, None) => Ok(ExitModel::None),
+            (Some(probability), None, None) => Ok(ExitModel::Probability(probability)),
+            (None, Some(threshold), None) => Ok(ExitModel::AprThreshold(threshold)),
+            (None, None, Some(schedule)) => Ok(ExitModel::Schedule(schedule)),
+            _ => Err(Error::config(
+                "choose either an exit probability, an exit APR threshold or an exit schedule",
+            )),
+        }
+    }
+}
+
+impl ScenarioTier {
+    fn get_default_probability() -> f32 {
+        1.0
+    }
+
+    fn check(&self) -> Result<()> {
+        Config::check_probability("probability online", self.online)?;
+        Config::check_probability("probability honest", self.honest)?;
+        Config::check_probability("probability inclusion", self.inclusion)?;
+
+        Ok(())
+    }
+}
+
+// the schedule of a section, inline or out of its file, sorted by epoch
+fn get_schedule<T: std::str::FromStr>(
+    schedule: &Option<String>,
+    schedule_file: &Option<String>,
+) -> Result<Option<Vec<(i32, T)>>> {
+    let mut schedule = match (schedule, schedule_file) {
+        (Some(_), Some(_)) => {
+            return Err(Error::config("choose either a schedule or a schedule file"))
+        }
+        (Some(schedule), None) => Config::parse_schedule(schedule)?,
+        (None, Some(file_name)) => Config::read_schedule_file(file_name)?,
+        (None, None) => return Ok(None),
+    };
+    schedule.sort_by_key(|(epoch, _)| *epoch);
+
+    Ok(Some(schedule))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let scenario = Scenario::parse(
+            r#"
+            epochs = 1000
+            initial-stake = 1000000
+            sweep = ["probability-online=0.9,0.95"]
+            output-format = "ndjson"
+            forks = "100:altair,200:bellatrix"
+
+            [[tiers]]
+            name = "pro"
+            share = 0.8
+            online = 0.99
+            inclusion = 0.95
+
+            [[tiers]]
+            name = "hobbyist"
+            share = 0.2
+            online = 0.9
+            honest = 0.5
+            inclusion = 0.85
+
+            [[my-validators]]
+            name = "mine"
+            balance = 32
+            online = 0.95
+            inclusion = 0.99
+
+            [slashing]
+            schedule = "100:10,200:5"
+
+            [deposits]
+            rate = 1000
+            arrivals = "poisson"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(Some(1000), scenario.epochs);
+        assert_eq!(Some(1_000_000), scenario.initial_stake);
+        assert_eq!(None, scenario.seed);
+        assert_eq!(Some("ndjson".to_string()), scenario.output_format);
+        assert_eq!(1, scenario.sweep.len());
+        assert_eq!(Some("100:altair,200:bellatrix".to_string()), scenario.forks);
+
+        let tiers = scenario.get_tiers().unwrap();
+        assert_eq!(2, tiers.len());
+        assert_eq!(1.0, tiers[0].probability_honest);
+        assert_eq!(0.5, tiers[1].probability_honest);
+
+        let tracked_validators = scenario.get_tracked_validators().unwrap();
+        assert_eq!(32_000_000_000, tracked_validators[0].balance);
+        assert_eq!(0.95, tracked_validators[0].tier.probability_online);
+
+        match scenario.get_slashing_model().unwrap() {
+            SlashingModel::Schedule(schedule) => assert_eq!(vec![(100, 10), (200, 5)], schedule),
+            _ => panic!("expected a slashing schedule"),
+        }
+        match scenario.get_deposit_model().unwrap() {
+            DepositModel::Poisson(rate) => assert_eq!(1000.0, rate),
+            _ => panic!("expected poisson deposits"),
+        }
+        match scenario.get_exit_model().unwrap() {
+            ExitModel::None => {}
+            _ => panic!("expected no exits"),
+        }
+    }
+
+    #[test]
+    fn read_example() {
+        let scenario = Scenario::read("scenarios/example.toml").unwrap();
+
+        assert_eq!(Some(81125), scenario.epochs);
+        assert!(scenario.get_tiers().unwrap().is_empty());
+        assert_eq!(1, scenario.get_tracked_validators().unwrap().len());
+    }
+
+    #[test]
+    fn parse_tier_without_inclusion() {
+        assert!(Scenario::parse("[[tiers]]\nname = \"A\"\nshare = 1.0\nonline = 0.9").is_err());
+    }
+
+    #[test]
+    fn parse_unknown_variable() {
+        assert!(Scenario::parse("epoch = 1000").is_err());
+    }
+
+    #[test]
+    fn exit_model_ambiguous() {
+        let scenario = Scenario::parse(
+            r#"
+            [exits]
+            probability = 0.01
+            apr-threshold = 2.5
+            "#,
+        )
+        .unwrap();
+
+        assert!(scenario.get_exit_model().is_err());
+    }
+
+    #[test]
+    fn parse_fields_of_another_section() {
+        assert!(Scenario::parse("[slashing]\nrate = 10.0").is_err());
+        assert!(Scenario::parse("[deposits]\nprobability = 0.01").is_err());
+        assert!(Scenario::parse("[exits]\narrivals = \"poisson\"").is_err());
+    }
+
+    #[test]
+    fn slashing_schedule_file() {
+        let scenario = Scenario::parse("[slashing]\nschedule-file = \"missing.csv\"").unwrap();
+
+        // the file is read, rather than ignored
+        assert!(scenario.get_slashing_model().is_err());
+    }
+}
@@ -0,0 +1,15 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// A slashing included on chain during the epoch
+//
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Copy, Clone, Debug)]
+pub struct Slashing {
+    pub validator_index: usize,
+    // the effective balance of the validator when it got slashed
+    pub effective_balance: u64,
+
+    // the block proposer including the slashing, who is also the whistleblower
+    pub proposer_index: usize,
+}
@@ -7,36 +7,237 @@
 ////////////////////////////////////////////////////////////////////////////////
 
 use super::config;
+use super::fork::ForkParameters;
 use super::validator::Validator;
 
+pub const JUSTIFICATION_BITS_LENGTH: usize = 4;
+
 pub struct State {
     // we keep the config at hand
     pub config: config::Config,
 
     pub validators: Vec<Validator>,
+
+    // SPEC: justification and finality checkpoints (epochs only)
+    pub justification_bits: [bool; JUSTIFICATION_BITS_LENGTH],
+    pub previous_justified_epoch: i32,
+    pub current_justified_epoch: i32,
+    pub finalized_epoch: i32,
+
+    // SPEC: effective balances slashed over the last EPOCHS_PER_SLASHINGS_VECTOR epochs
+    pub slashings: Vec<u64>,
+
+    // tail of the exit queue: exit epochs are handed out in order,
+    //   so we don't need to go through all the validators for each exit
+    pub exit_queue_epoch: i32,
+    pub exit_queue_churn: u64,
+
+    // SPEC (altair): the validators of the current sync committee (with
+    //   repeats), picked at the start of each period
+    pub sync_committee: Vec<usize>,
+
+    // deposited ETH (in Gwei) not yet enough for a new validator
+    pub pending_deposits: u64,
+    // all the ETH (in Gwei) deposited since the start of the simulation
+    pub total_deposited: u64,
 }
 
 impl State {
     pub fn new(config: config::Config) -> State {
         // println!("config.total_at_stake_initial STATE {}", config.total_at_stake_initial);
-        let number_of_validators = config.total_at_stake_initial / config::MAX_EFFECTIVE_BALANCE;
+        let number_of_validators = config.total_at_stake_initial / config::MIN_ACTIVATION_BALANCE;
         // println!("Number of validators {}", number_of_validators);
         let mut validators = vec![];
 
-        for _ in 0..number_of_validators {
+        // SPEC: genesis validators are activated right away
+        for index in 0..number_of_validators {
+            let position = index as f32 / number_of_validators as f32;
+
             validators.push(Validator {
-                balance: config::MAX_EFFECTIVE_BALANCE,
-                effective_balance: config::MAX_EFFECTIVE_BALANCE,
+                activation_eligibility_epoch: config::GENESIS_EPOCH,
+                activation_epoch: config::GENESIS_EPOCH,
+                is_active: true,
+                tier: config.get_tier_by_position(position),
+                ..Validator::new(config::MIN_ACTIVATION_BALANCE)
+            });
+        }
 
+        // tracked validators join the genesis ones, with their own balance
+        for (index, tracked_validator) in config.tracked_validators.iter().enumerate() {
+            validators.push(Validator {
+                activation_eligibility_epoch: config::GENESIS_EPOCH,
+                activation_epoch: config::GENESIS_EPOCH,
                 is_active: true,
-                is_slashed: false,
+                tracked: Some(index),
+                ..Validator::new(tracked_validator.balance)
             });
         }
 
         State {
-            config: config,
-            validators: validators,
+            config,
+            validators,
+            justification_bits: [false; JUSTIFICATION_BITS_LENGTH],
+            previous_justified_epoch: 0,
+            current_justified_epoch: 0,
+            finalized_epoch: 0,
+            slashings: vec![0; config::EPOCHS_PER_SLASHINGS_VECTOR as usize],
+            exit_queue_epoch: config::GENESIS_EPOCH,
+            exit_queue_churn: 0,
+            sync_committee: vec![],
+            pending_deposits: 0,
+            total_deposited: 0,
+        }
+    }
+
+    // SPEC: compute_activation_exit_epoch()
+    pub fn compute_activation_exit_epoch(epoch_id: i32) -> i32 {
+        epoch_id + 1 + config::MAX_SEED_LOOKAHEAD
+    }
+
+    // SPEC: get_validator_churn_limit() (get_balance_churn_limit() since electra)
+    pub fn get_validator_churn_limit(&self, parameters: &ForkParameters) -> u64 {
+        if parameters.is_balance_churn {
+            let churn = std::cmp::max(
+                parameters.min_per_epoch_churn_limit,
+                self.get_total_active_balance() / parameters.churn_limit_quotient,
+            );
+            churn - churn % config::EFFECTIVE_BALANCE_INCREMENT
+        } else {
+            std::cmp::max(
+                parameters.min_per_epoch_churn_limit,
+                self.get_total_active_validators() / parameters.churn_limit_quotient,
+            )
+        }
+    }
+
+    // SPEC (deneb): get_validator_activation_churn_limit()
+    pub fn get_activation_churn_limit(&self, parameters: &ForkParameters) -> u64 {
+        std::cmp::min(
+            parameters.max_per_epoch_activation_churn_limit,
+            self.get_validator_churn_limit(parameters),
+        )
+    }
+
+    // SPEC (electra): get_activation_exit_churn_limit()
+    pub fn get_exit_churn_limit(&self, parameters: &ForkParameters) -> u64 {
+        std::cmp::min(
+            parameters.max_per_epoch_exit_churn_limit,
+            self.get_validator_churn_limit(parameters),
+        )
+    }
+
+    // SPEC: initiate_validator_exit() (compute_exit_epoch_and_update_churn()
+    //   since electra)
+    pub fn initiate_validator_exit(
+        &mut self,
+        index: usize,
+        epoch_id: i32,
+        churn_limit: u64,
+        parameters: &ForkParameters,
+    ) {
+        if self.validators[index].exit_epoch != config::FAR_FUTURE_EPOCH {
+            return;
+        }
+
+        // compute the exit queue epoch
+        let activation_exit_epoch = State::compute_activation_exit_epoch(epoch_id);
+        if self.exit_queue_epoch < activation_exit_epoch {
+            self.exit_queue_epoch = activation_exit_epoch;
+            self.exit_queue_churn = 0;
+        }
+
+        // what doesn't fit in the churn left goes to the next epochs
+        //   (several of them for a balance larger than the churn limit)
+        let churn = parameters.get_churn(&self.validators[index]);
+        let churn_left = churn_limit.saturating_sub(self.exit_queue_churn);
+        if churn > churn_left {
+            let remaining_churn = churn - churn_left;
+            let additional_epochs = (remaining_churn - 1) / churn_limit + 1;
+            self.exit_queue_epoch += additional_epochs as i32;
+            self.exit_queue_churn = remaining_churn - (additional_epochs - 1) * churn_limit;
+        } else {
+            self.exit_queue_churn += churn;
         }
+
+        // set validator exit epoch and withdrawable epoch
+        let validator = &mut self.validators[index];
+        validator.exit_epoch = self.exit_queue_epoch;
+        validator.withdrawable_epoch =
+            validator.exit_epoch + config::MIN_VALIDATOR_WITHDRAWABILITY_DELAY;
+    }
+
+    // validators waiting to be activated
+    pub fn get_activation_queue_length(&self, epoch_id: i32) -> u64 {
+        self.validators
+            .iter()
+            .map(|v: &Validator| {
+                if !v.is_slashed
+                    && v.exit_epoch == config::FAR_FUTURE_EPOCH
+                    && v.activation_epoch > epoch_id
+                {
+                    1
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    // validators that requested their exit but are still active
+    pub fn get_exit_queue_length(&self, epoch_id: i32) -> u64 {
+        self.validators
+            .iter()
+            .map(|v: &Validator| {
+                if v.exit_epoch != config::FAR_FUTURE_EPOCH && v.exit_epoch > epoch_id {
+                    1
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    pub fn get_total_slashings(&self) -> u64 {
+        self.slashings.iter().sum()
+    }
+
+    pub fn get_total_slashed_validators(&self) -> u64 {
+        self.validators
+            .iter()
+            .map(|v: &Validator| if v.is_slashed { 1 } else { 0 })
+            .sum()
+    }
+
+    pub fn get_finality_delay(&self, epoch_id: i32) -> u64 {
+        let previous_epoch = std::cmp::max(epoch_id - 1, 0);
+
+        std::cmp::max(previous_epoch - self.finalized_epoch, 0) as u64
+    }
+
+    pub fn get_total_validators_in_tier(&self, tier: usize) -> u64 {
+        self.validators
+            .iter()
+            .map(|v: &Validator| {
+                if v.tier == tier && v.tracked.is_none() {
+                    1
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    pub fn get_total_staked_balance_in_tier(&self, tier: usize) -> u64 {
+        self.validators
+            .iter()
+            .map(|v: &Validator| {
+                if v.tier == tier && v.tracked.is_none() {
+                    v.balance
+                } else {
+                    0
+                }
+            })
+            .sum()
     }
 
     pub fn get_total_staked_balance(&self) -> u64 {
@@ -69,7 +270,7 @@ impl State {
         self.validators
             .iter()
             .map(|v: &Validator| {
-                if v.is_active && !v.is_slashed {
+                if v.is_active && !v.is_slashed && v.has_attested {
                     v.effective_balance
                 } else {
                     0
@@ -78,6 +279,23 @@ impl State {
             .sum()
     }
 
+    // SPEC (altair): get_unslashed_participating_indices() balance, for each flag
+    pub fn get_unslashed_participating_balances(&self) -> [u64; 3] {
+        let mut balances = [0; 3];
+
+        for v in self.validators.iter() {
+            if v.is_active && !v.is_slashed {
+                for (flag_index, balance) in balances.iter_mut().enumerate() {
+                    if v.participation_flags[flag_index] {
+                        *balance += v.effective_balance;
+                    }
+                }
+            }
+        }
+
+        balances
+    }
+
     pub fn get_max_balance(&self) -> u64 {
         self.validators
             .iter()
@@ -89,7 +307,90 @@ impl State {
         self.validators
             .iter()
             .map(|v: &Validator| v.balance)
-            .fold(std::u64::MAX, std::cmp::min)
+            .fold(u64::MAX, std::cmp::min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::fork::{ELECTRA, PHASE0};
+
+    #[test]
+    fn tracked_validators_join_at_genesis() {
+        let config = config::Config::builder()
+            .tracked_validator(config::TrackedValidator::new(
+                "mine",
+                40_000_000_000,
+                1.0,
+                1.0,
+                1.0,
+            ))
+            .build()
+            .unwrap();
+        let number_of_validators = config.total_at_stake_initial / config::MIN_ACTIVATION_BALANCE;
+
+        let state = State::new(config);
+        let tracked = state.validators.last().unwrap();
+
+        assert_eq!(number_of_validators as usize + 1, state.validators.len());
+        assert_eq!(Some(0), tracked.tracked);
+        assert_eq!(40_000_000_000, tracked.balance);
+        assert_eq!(config::MIN_ACTIVATION_BALANCE, tracked.effective_balance);
+        assert!(tracked.is_active_validator(config::GENESIS_EPOCH));
+        assert_eq!(
+            number_of_validators,
+            state.get_total_validators_in_tier(tracked.tier)
+        );
+    }
+
+    #[test]
+    fn exit_queue_churn() {
+        let mut state = State::new(config::Config::default());
+
+        // 4 validators per epoch as much
+        for index in 0..10 {
+            state.initiate_validator_exit(index, 10, 4, &PHASE0);
+        }
+
+        let exit_epochs: Vec<i32> = state.validators[0..10]
+            .iter()
+            .map(|v| v.exit_epoch)
+            .collect();
+        assert_eq!(vec![15, 15, 15, 15, 16, 16, 16, 16, 17, 17], exit_epochs);
+        assert_eq!(
+            17 + config::MIN_VALIDATOR_WITHDRAWABILITY_DELAY,
+            state.validators[9].withdrawable_epoch
+        );
+
+        // exiting twice changes nothing
+        state.initiate_validator_exit(0, 20, 4, &PHASE0);
+        assert_eq!(15, state.validators[0].exit_epoch);
+
+        // a later exit starts a new queue
+        state.initiate_validator_exit(10, 20, 4, &PHASE0);
+        assert_eq!(25, state.validators[10].exit_epoch);
+    }
+
+    #[test]
+    fn exit_queue_balance_churn() {
+        let mut state = State::new(config::Config::default());
+
+        // 48 ETH per epoch: a 32 ETH exit can take what's left of one epoch
+        //   and some of the next
+        for index in 0..4 {
+            state.initiate_validator_exit(index, 10, 48_000_000_000, &ELECTRA);
+        }
+
+        let exit_epochs: Vec<i32> = state.validators[0..4]
+            .iter()
+            .map(|v| v.exit_epoch)
+            .collect();
+        assert_eq!(vec![15, 16, 16, 17], exit_epochs);
+
+        // 500,000 ETH is under 128 ETH * 65,536
+        assert_eq!(128_000_000_000, state.get_validator_churn_limit(&ELECTRA));
+        assert_eq!(4, state.get_validator_churn_limit(&PHASE0));
     }
 }
 
@@ -0,0 +1,253 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Aggregates the runs of a simulation (Monte Carlo repetitions)
+//   into statistics of each metric, at each epoch (or month)
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use super::output::{EpochReportRow, MonthlyReportRow};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// report rows whose metrics can be aggregated across runs
+pub trait Metrics {
+    // the epoch (or month) of the row
+    fn get_period(&self) -> i32;
+    fn get_metrics(&self) -> Vec<(&'static str, f64)>;
+}
+
+impl Metrics for EpochReportRow {
+    fn get_period(&self) -> i32 {
+        self.epoch_id
+    }
+
+    fn get_metrics(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("deltas_head_ffg_rewards", self.deltas_head_ffg_rewards as f64),
+            ("deltas_head_ffg_penalties", self.deltas_head_ffg_penalties as f64),
+            ("deltas_proposer_rewards", self.deltas_proposer_rewards as f64),
+            ("deltas_attester_rewards", self.deltas_attester_rewards as f64),
+            ("deltas_inactivity_penalties", self.deltas_inactivity_penalties as f64),
+            ("deltas_slashing_penalties", self.deltas_slashing_penalties as f64),
+            ("deltas_midway_penalties", self.deltas_midway_penalties as f64),
+            ("deltas_whistleblower_rewards", self.deltas_whistleblower_rewards as f64),
+            (
+                "deltas_slashing_proposer_rewards",
+                self.deltas_slashing_proposer_rewards as f64,
+            ),
+            (
+                "deltas_sync_committee_rewards",
+                self.deltas_sync_committee_rewards as f64,
+            ),
+            (
+                "deltas_sync_committee_penalties",
+                self.deltas_sync_committee_penalties as f64,
+            ),
+            ("total_staked_balance", self.total_staked_balance as f64),
+            ("total_effective_balance", self.total_effective_balance as f64),
+            ("max_balance", self.max_balance as f64),
+            ("min_balance", self.min_balance as f64),
+            ("total_validators", self.total_validators as f64),
+            ("total_active_validators", self.total_active_validators as f64),
+            ("deposits", self.deposits as f64),
+            ("total_deposited_balance", self.total_deposited_balance as f64),
+            ("activation_queue_length", self.activation_queue_length as f64),
+            ("voluntary_exits", self.voluntary_exits as f64),
+            ("exit_queue_length", self.exit_queue_length as f64),
+            ("total_slashed_validators", self.total_slashed_validators as f64),
+            ("finalized_epoch", self.finalized_epoch as f64),
+            ("finality_delay", self.finality_delay as f64),
+            ("time_elapsed", self.time_elapsed as f64),
+        ]
+    }
+}
+
+impl Metrics for MonthlyReportRow {
+    fn get_period(&self) -> i32 {
+        self.month_number as i32
+    }
+
+    fn get_metrics(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("network_percentage_rewards", self.network_percentage_rewards),
+            ("network_percentage_penalties", self.network_percentage_penalties),
+            ("network_percentage_net_rewards", self.network_percentage_net_rewards),
+        ]
+    }
+}
+
+// a statistic of a metric at an epoch (or month), across the runs
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SummaryRow {
+    pub simulation_id: u64,
+    pub parameters: String,
+    pub period: i32,
+    pub metric: String,
+    // mean, std, min, max or a percentile (e.g. p95)
+    pub statistic: String,
+    pub value: f64,
+}
+
+pub struct Summary {
+    percentiles: Vec<f64>,
+    runs: u64,
+    // the values of each metric across the runs, for each period
+    values: BTreeMap<i32, Vec<(&'static str, Vec<f64>)>>,
+}
+
+impl Summary {
+    pub fn new(percentiles: &[f64]) -> Summary {
+        Summary {
+            percentiles: percentiles.to_vec(),
+            runs: 0,
+            values: BTreeMap::new(),
+        }
+    }
+
+    pub fn add<T: Metrics>(&mut self, row: &T) {
+        let metrics = row.get_metrics();
+        let values = self.values.entry(row.get_period()).or_insert_with(|| {
+            metrics
+                .iter()
+                .map(|(metric, _)| (*metric, vec![]))
+                .collect()
+        });
+
+        for (index, (_, value)) in metrics.iter().enumerate() {
+            values[index].1.push(*value);
+        }
+    }
+
+    // all the rows of a run were added
+    pub fn end_run(&mut self) {
+        self.runs += 1;
+    }
+
+    pub fn get_runs(&self) -> u64 {
+        self.runs
+    }
+
+    // the statistics, after which the summary starts over
+    pub fn take_rows(&mut self, simulation_id: u64, parameters: &str) -> Vec<SummaryRow> {
+        let mut rows = vec![];
+
+        for (period, metrics) in self.values.iter_mut() {
+            for (metric, values) in metrics.iter_mut() {
+                values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+                let mut statistics = vec![
+                    ("mean".to_string(), Summary::get_mean(values)),
+                    ("std".to_string(), Summary::get_std(values)),
+                    ("min".to_string(), values[0]),
+                    ("max".to_string(), values[values.len() - 1]),
+                ];
+                for percentile in self.percentiles.iter() {
+                    statistics.push((
+                        format!("p{}", percentile),
+                        Summary::get_percentile(values, *percentile),
+                    ));
+                }
+
+                for (statistic, value) in statistics {
+                    rows.push(SummaryRow {
+                        simulation_id,
+                        parameters: parameters.to_string(),
+                        period: *period,
+                        metric: metric.to_string(),
+                        statistic,
+                        value,
+                    });
+                }
+            }
+        }
+
+        self.values.clear();
+        self.runs = 0;
+
+        rows
+    }
+
+    fn get_mean(values: &[f64]) -> f64 {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+
+    // sample standard deviation
+    fn get_std(values: &[f64]) -> f64 {
+        if values.len() < 2 {
+            return 0.0;
+        }
+
+        let mean = Summary::get_mean(values);
+        let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>()
+            / (values.len() - 1) as f64;
+
+        variance.sqrt()
+    }
+
+    // linear interpolation between the closest ranks, out of sorted values
+    fn get_percentile(sorted_values: &[f64], percentile: f64) -> f64 {
+        let rank = percentile / 100.0 * (sorted_values.len() - 1) as f64;
+        let lower = rank.floor() as usize;
+        let upper = rank.ceil() as usize;
+
+        sorted_values[lower] + (sorted_values[upper] - sorted_values[lower]) * rank.fract()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn epoch_report_row(epoch_id: i32, deposits: u64) -> EpochReportRow {
+        EpochReportRow {
+            epoch_id,
+            deposits,
+            ..EpochReportRow::new()
+        }
+    }
+
+    fn get_value(rows: &[SummaryRow], period: i32, metric: &str, statistic: &str) -> f64 {
+        rows.iter()
+            .find(|row| row.period == period && row.metric == metric && row.statistic == statistic)
+            .unwrap()
+            .value
+    }
+
+    #[test]
+    fn statistics_across_runs() {
+        let mut summary = Summary::new(&[50.0, 95.0]);
+
+        for deposits in [4, 1, 3, 2].iter() {
+            summary.add(&epoch_report_row(0, *deposits));
+            summary.add(&epoch_report_row(1, 10));
+            summary.end_run();
+        }
+        assert_eq!(4, summary.get_runs());
+
+        let rows = summary.take_rows(7, "epochs=2");
+
+        assert_eq!(2.5, get_value(&rows, 0, "deposits", "mean"));
+        assert!((get_value(&rows, 0, "deposits", "std") - 1.290_994).abs() < 1e-6);
+        assert_eq!(1.0, get_value(&rows, 0, "deposits", "min"));
+        assert_eq!(4.0, get_value(&rows, 0, "deposits", "max"));
+        assert_eq!(2.5, get_value(&rows, 0, "deposits", "p50"));
+        assert!((get_value(&rows, 0, "deposits", "p95") - 3.85).abs() < 1e-9);
+        assert_eq!(0.0, get_value(&rows, 1, "deposits", "std"));
+        assert_eq!(7, rows[0].simulation_id);
+        assert_eq!("epochs=2", rows[0].parameters);
+
+        // it starts over
+        assert_eq!(0, summary.get_runs());
+        assert!(summary.take_rows(7, "epochs=2").is_empty());
+    }
+
+    #[test]
+    fn get_percentile() {
+        let values = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+
+        assert_eq!(1.0, Summary::get_percentile(&values, 0.0));
+        assert_eq!(3.0, Summary::get_percentile(&values, 50.0));
+        assert_eq!(5.0, Summary::get_percentile(&values, 100.0));
+        assert_eq!(1.5, Summary::get_percentile(&values, 12.5));
+    }
+}
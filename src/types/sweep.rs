@@ -0,0 +1,259 @@
+////////////////////////////////////////////////////////////////////////////////
+//
+// Parameter sweeps: a simulation for each combination of parameter values
+//
+////////////////////////////////////////////////////////////////////////////////
+
+use super::config::Config;
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SweepParameter {
+    // in ETH
+    InitialStake,
+    ProbabilityOnline,
+    ProbabilityHonest,
+    Epochs,
+}
+
+impl SweepParameter {
+    fn parse(name: &str) -> Result<SweepParameter> {
+        match name {
+            "initial-stake" => Ok(SweepParameter::InitialStake),
+            "probability-online" => Ok(SweepParameter::ProbabilityOnline),
+            "probability-honest" => Ok(SweepParameter::ProbabilityHonest),
+            "epochs" => Ok(SweepParameter::Epochs),
+            _ => Err(Error::invalid_value(
+                "sweep parameter",
+                name,
+                "initial-stake, probability-online, probability-honest or epochs",
+            )),
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SweepParameter::InitialStake => "initial-stake",
+            SweepParameter::ProbabilityOnline => "probability-online",
+            SweepParameter::ProbabilityHonest => "probability-honest",
+            SweepParameter::Epochs => "epochs",
+        }
+    }
+
+    // the values apply() can take, whichever way the axis was made
+    pub fn validate(&self, value: f64) -> Result<()> {
+        let is_valid = match self {
+            // in Gwei it should still fit in a u64
+            SweepParameter::InitialStake => {
+                value >= 500_000.0 && (value as u64).checked_mul(1_000_000_000).is_some()
+            }
+            SweepParameter::ProbabilityOnline | SweepParameter::ProbabilityHonest => {
+                (0.0..=1.0).contains(&value)
+            }
+            SweepParameter::Epochs => {
+                value >= 1.0 && value <= i32::MAX as f64 && value.fract() == 0.0
+            }
+        };
+
+        if !is_valid {
+            let expected = match self {
+                SweepParameter::InitialStake => "an amount of ETH in [500000, 18446744073]",
+                SweepParameter::ProbabilityOnline | SweepParameter::ProbabilityHonest => {
+                    "a value in [0,1]"
+                }
+                SweepParameter::Epochs => "a positive integer",
+            };
+            return Err(Error::invalid_value(
+                self.get_name(),
+                &value.to_string(),
+                expected,
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn apply(&self, config: &mut Config, value: f64) {
+        match self {
+            SweepParameter::InitialStake => {
+                config.total_at_stake_initial = value as u64 * 1_000_000_000;
+            }
+            SweepParameter::ProbabilityOnline => {
+                for tier in config.tiers.iter_mut() {
+                    tier.probability_online = value as f32;
+                }
+            }
+            SweepParameter::ProbabilityHonest => {
+                for tier in config.tiers.iter_mut() {
+                    tier.probability_honest = value as f32;
+                }
+            }
+            SweepParameter::Epochs => config.epochs = value as i32,
+        }
+    }
+}
+
+// the values a parameter goes through
+#[derive(Debug, Clone)]
+pub struct SweepAxis {
+    pub parameter: SweepParameter,
+    pub values: Vec<f64>,
+}
+
+impl SweepAxis {
+    // parses "name=start..end:step" (both ends included) or "name=value,value,..."
+    pub fn parse(axis: &str) -> Result<SweepAxis> {
+        let mut parts = axis.splitn(2, '=');
+        let name = parts.next().unwrap_or("").trim();
+        let values = match parts.next() {
+            Some(values) => values.trim(),
+            None => {
+                return Err(Error::invalid_value(
+                    "sweep",
+                    axis,
+                    "name=start..end:step or name=value,value,...",
+                ))
+            }
+        };
+
+        let parameter = SweepParameter::parse(name)?;
+        let values = if values.contains("..") {
+            SweepAxis::parse_range(values)?
+        } else {
+            values
+                .split(',')
+                .map(SweepAxis::parse_value)
+                .collect::<Result<_>>()?
+        };
+
+        for value in values.iter() {
+            parameter.validate(*value)?;
+        }
+
+        Ok(SweepAxis { parameter, values })
+    }
+
+    pub fn range(parameter: SweepParameter, start: f64, end: f64, step: f64) -> Result<SweepAxis> {
+        if step <= 0.0 || end < start {
+            return Err(Error::config(
+                "sweep ranges should go up from start to end with a positive step",
+            ));
+        }
+
+        // a small tolerance, so floating point steps don't miss the end
+        let steps = ((end - start) / step + 1e-9).floor() as u64;
+        let values = (0..=steps).map(|i| start + i as f64 * step).collect();
+
+        Ok(SweepAxis { parameter, values })
+    }
+
+    fn parse_range(range: &str) -> Result<Vec<f64>> {
+        let mut bounds = range.splitn(2, "..");
+        let start = SweepAxis::parse_value(bounds.next().unwrap_or(""))?;
+        let mut end_and_step = bounds.next().unwrap_or("").splitn(2, ':');
+        let end = SweepAxis::parse_value(end_and_step.next().unwrap_or(""))?;
+        let step = match end_and_step.next() {
+            Some(step) => SweepAxis::parse_value(step)?,
+            None => {
+                return Err(Error::invalid_value(
+                    "sweep range",
+                    range,
+                    "start..end:step",
+                ))
+            }
+        };
+
+        // the parameter doesn't matter here
+        Ok(SweepAxis::range(SweepParameter::Epochs, start, end, step)?.values)
+    }
+
+    fn parse_value(value: &str) -> Result<f64> {
+        Config::parse_value("sweep value", value, "a number")
+    }
+}
+
+// all the combinations of the values of the axes (cartesian product),
+//   the first axis changing the slowest
+pub fn get_combinations(axes: &[SweepAxis]) -> Vec<Vec<(SweepParameter, f64)>> {
+    let mut combinations: Vec<Vec<(SweepParameter, f64)>> = vec![vec![]];
+
+    for axis in axes {
+        combinations = combinations
+            .iter()
+            .flat_map(|combination| {
+                axis.values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.push((axis.parameter, *value));
+                    combination
+                })
+            })
+            .collect();
+    }
+
+    combinations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_range() {
+        let axis = SweepAxis::parse("probability-online = 0.9..1.0:0.05").unwrap();
+
+        assert_eq!(SweepParameter::ProbabilityOnline, axis.parameter);
+        assert_eq!(3, axis.values.len());
+        assert!((axis.values[2] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parse_list() {
+        let axis = SweepAxis::parse("initial-stake=500000,1000000,2000000").unwrap();
+
+        assert_eq!(SweepParameter::InitialStake, axis.parameter);
+        assert_eq!(vec![500_000.0, 1_000_000.0, 2_000_000.0], axis.values);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(SweepAxis::parse("probability-online=0.5,1.5").is_err());
+        assert!(SweepAxis::parse("probability-online=0.5,x").is_err());
+        assert!(SweepAxis::parse("epochs=10..20").is_err());
+        assert!(SweepAxis::parse("stake=1,2").is_err());
+        assert!(SweepAxis::parse("initial-stake=500000,100000000000").is_err());
+    }
+
+    #[test]
+    fn get_combinations_cartesian_product() {
+        let axes = vec![
+            SweepAxis::parse("epochs=10,20").unwrap(),
+            SweepAxis::parse("probability-online=0.5,0.75,1").unwrap(),
+        ];
+
+        let combinations = get_combinations(&axes);
+
+        assert_eq!(6, combinations.len());
+        assert_eq!(
+            vec![
+                (SweepParameter::Epochs, 10.0),
+                (SweepParameter::ProbabilityOnline, 0.75)
+            ],
+            combinations[1]
+        );
+        assert_eq!(
+            vec![
+                (SweepParameter::Epochs, 20.0),
+                (SweepParameter::ProbabilityOnline, 1.0)
+            ],
+            combinations[5]
+        );
+    }
+
+    #[test]
+    fn get_combinations_no_axes() {
+        assert_eq!(
+            vec![Vec::<(SweepParameter, f64)>::new()],
+            get_combinations(&[])
+        );
+    }
+}
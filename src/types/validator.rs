@@ -5,23 +5,150 @@
 ////////////////////////////////////////////////////////////////////////////////
 
 use super::config;
+use super::fork::ForkParameters;
+use serde::Serialize;
 use std::cmp;
+use std::fmt;
 
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidatorStatus {
+    Pending,
+    #[default]
+    Active,
+    Exiting,
+    Exited,
+    Slashed,
+    Withdrawable,
+}
+
+impl fmt::Display for ValidatorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match self {
+            ValidatorStatus::Pending => "pending",
+            ValidatorStatus::Active => "active",
+            ValidatorStatus::Exiting => "exiting",
+            ValidatorStatus::Exited => "exited",
+            ValidatorStatus::Slashed => "slashed",
+            ValidatorStatus::Withdrawable => "withdrawable",
+        };
+
+        write!(f, "{}", status)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
 pub struct Validator {
     pub balance: u64,
     pub effective_balance: u64,
     pub is_active: bool,
     pub is_slashed: bool,
+
+    // index of its tier in the config
+    pub tier: usize,
+    // index of its entry in the config, if we trace it
+    pub tracked: Option<usize>,
+
+    pub activation_eligibility_epoch: i32,
+    pub activation_epoch: i32,
+    pub exit_epoch: i32,
+    pub withdrawable_epoch: i32,
+
+    // the validator was online and honest during the previous epoch,
+    //   so its attestation matched the source, target and head votes
+    pub has_attested: bool,
+    // SPEC (altair): timely source, target and head of that attestation
+    pub participation_flags: [bool; 3],
+    // SPEC (altair): grows while it misses the target during a leak
+    pub inactivity_score: u64,
 }
 
 impl Validator {
-    pub fn get_base_reward(&self, sqrt_total_active_balance: u64) -> u64 {
-        self.effective_balance * config::BASE_REWARD_FACTOR
+    // a freshly deposited validator, waiting to be activated
+    pub fn new(balance: u64) -> Validator {
+        Validator {
+            balance,
+            effective_balance: cmp::min(
+                balance - balance % config::EFFECTIVE_BALANCE_INCREMENT,
+                config::MIN_ACTIVATION_BALANCE,
+            ),
+            is_active: false,
+            is_slashed: false,
+            tier: 0,
+            tracked: None,
+            activation_eligibility_epoch: config::FAR_FUTURE_EPOCH,
+            activation_epoch: config::FAR_FUTURE_EPOCH,
+            exit_epoch: config::FAR_FUTURE_EPOCH,
+            withdrawable_epoch: config::FAR_FUTURE_EPOCH,
+            has_attested: true,
+            participation_flags: [true; 3],
+            inactivity_score: 0,
+        }
+    }
+
+    // SPEC: is_active_validator()
+    pub fn is_active_validator(&self, epoch_id: i32) -> bool {
+        self.activation_epoch <= epoch_id && epoch_id < self.exit_epoch
+    }
+
+    pub fn get_status(&self, epoch_id: i32) -> ValidatorStatus {
+        if epoch_id < self.activation_epoch {
+            ValidatorStatus::Pending
+        } else if epoch_id >= self.withdrawable_epoch {
+            ValidatorStatus::Withdrawable
+        } else if self.is_slashed {
+            ValidatorStatus::Slashed
+        } else if epoch_id >= self.exit_epoch {
+            ValidatorStatus::Exited
+        } else if self.exit_epoch != config::FAR_FUTURE_EPOCH {
+            ValidatorStatus::Exiting
+        } else {
+            ValidatorStatus::Active
+        }
+    }
+
+    // annual percentage rate since activation, out of a 32 ETH deposit
+    pub fn get_apr(&self, epoch_id: i32) -> f64 {
+        if epoch_id <= self.activation_epoch {
+            return 0.0;
+        }
+
+        let earned = self.balance as f64 - config::MIN_ACTIVATION_BALANCE as f64;
+        let years = (epoch_id - self.activation_epoch) as f64 / config::EPOCHS_PER_YEAR;
+
+        earned / config::MIN_ACTIVATION_BALANCE as f64 / years * 100.0
+    }
+
+    // SPEC: a slashed validator keeps being penalized until it can withdraw
+    pub fn is_eligible(&self, epoch_id: i32) -> bool {
+        self.is_active || (self.is_slashed && epoch_id < self.withdrawable_epoch)
+    }
+
+    pub fn get_base_reward(
+        &self,
+        sqrt_total_active_balance: u64,
+        parameters: &ForkParameters,
+    ) -> u64 {
+        self.effective_balance * parameters.base_reward_factor
             / sqrt_total_active_balance
             / config::BASE_REWARDS_PER_EPOCH
     }
 
-    pub fn update_effective_balance(&mut self) {
+    // SPEC (altair): get_base_reward_per_increment()
+    pub fn get_base_reward_per_increment(
+        sqrt_total_active_balance: u64,
+        parameters: &ForkParameters,
+    ) -> u64 {
+        config::EFFECTIVE_BALANCE_INCREMENT * parameters.base_reward_factor
+            / sqrt_total_active_balance
+    }
+
+    // SPEC (altair): get_base_reward(), a base reward per increment of effective balance
+    pub fn get_altair_base_reward(&self, base_reward_per_increment: u64) -> u64 {
+        self.effective_balance / config::EFFECTIVE_BALANCE_INCREMENT * base_reward_per_increment
+    }
+
+    pub fn update_effective_balance(&mut self, parameters: &ForkParameters) {
         let half_increment = config::EFFECTIVE_BALANCE_INCREMENT / 2;
 
         if self.balance < self.effective_balance
@@ -29,7 +156,7 @@ impl Validator {
         {
             self.effective_balance = cmp::min(
                 self.balance - self.balance % config::EFFECTIVE_BALANCE_INCREMENT,
-                config::MAX_EFFECTIVE_BALANCE,
+                parameters.max_effective_balance,
             );
         }
     }
@@ -38,20 +165,65 @@ impl Validator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::fork::PHASE0;
 
     #[test]
     fn get_base_reward() {
-        let validator = Validator {
-            balance: 32_000_000_000,
-            effective_balance: 32_000_000_000,
-            is_active: true,
-            is_slashed: false,
-        };
+        let validator = Validator::new(32_000_000_000);
 
         // we pick sqrt of 500,000 ETH
         let sqrt_total_active_balance: u64 = 22_360_679;
 
-        assert_eq!(22_897, validator.get_base_reward(sqrt_total_active_balance));
+        assert_eq!(
+            22_897,
+            validator.get_base_reward(sqrt_total_active_balance, &PHASE0)
+        );
+    }
+
+    #[test]
+    fn get_altair_base_reward() {
+        let validator = Validator::new(32_000_000_000);
+
+        // 64 ETH / sqrt of 500,000 ETH (in Gwei) per increment
+        let base_reward_per_increment =
+            Validator::get_base_reward_per_increment(22_360_679, &PHASE0);
+
+        assert_eq!(2_862, base_reward_per_increment);
+        assert_eq!(
+            91_584,
+            validator.get_altair_base_reward(base_reward_per_increment)
+        );
+    }
+
+    #[test]
+    fn get_apr() {
+        let mut validator = Validator::new(32_000_000_000);
+        validator.activation_epoch = 0;
+
+        // 1.6 ETH in half a year
+        validator.balance = 33_600_000_000;
+        let half_a_year = (config::EPOCHS_PER_YEAR / 2.0) as i32;
+
+        assert_eq!(10.0, validator.get_apr(half_a_year).round());
+        assert_eq!(0.0, validator.get_apr(0));
+    }
+
+    #[test]
+    fn get_status() {
+        let mut validator = Validator::new(32_000_000_000);
+        assert_eq!(ValidatorStatus::Pending, validator.get_status(0));
+
+        validator.activation_epoch = 0;
+        assert_eq!(ValidatorStatus::Active, validator.get_status(0));
+
+        validator.exit_epoch = 10;
+        validator.withdrawable_epoch = 20;
+        assert_eq!(ValidatorStatus::Exiting, validator.get_status(5));
+        assert_eq!(ValidatorStatus::Exited, validator.get_status(10));
+        assert_eq!(ValidatorStatus::Withdrawable, validator.get_status(20));
+
+        validator.is_slashed = true;
+        assert_eq!(ValidatorStatus::Slashed, validator.get_status(10));
     }
 
     struct TestCaseUpdateBalance {
@@ -60,7 +232,7 @@ mod tests {
     }
 
     fn eth_to_gwei(eth_number: f64) -> u64 {
-        (eth_number * 1_000_000_000 as f64) as u64
+        (eth_number * 1_000_000_000f64) as u64
     }
 
     fn prepare_test_case_update_balance(
@@ -72,8 +244,7 @@ mod tests {
             validator: Validator {
                 balance: eth_to_gwei(balance),
                 effective_balance: eth_to_gwei(effective_balance),
-                is_active: true,
-                is_slashed: false,
+                ..Validator::new(0)
             },
             expected_result: eth_to_gwei(expected_result),
         }
@@ -113,7 +284,7 @@ mod tests {
         cases.push(prepare_test_case_update_balance(32.500001, 31.0, 32.0));
 
         for mut case in cases {
-            case.validator.update_effective_balance();
+            case.validator.update_effective_balance(&PHASE0);
             assert_eq!(case.expected_result, case.validator.effective_balance);
         }
     }